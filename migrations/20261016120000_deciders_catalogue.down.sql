@@ -0,0 +1,4 @@
+INSERT INTO deciders
+VALUES ('Restaurant', 'RestaurantOrderPlaced'),
+       ('Restaurant', 'RestaurantOrderNotPlaced')
+ON CONFLICT DO NOTHING;
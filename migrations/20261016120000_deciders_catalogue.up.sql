@@ -0,0 +1,13 @@
+-- The Restaurant decider publishes `OrderPlaced`/`OrderNotPlaced`, but `RestaurantOrderPlaced`/`RestaurantOrderNotPlaced` were registered by the `data` migration.
+-- The (decider, event) catalogue is registered by the application at startup (from the code), so the stale registrations are removed.
+-- `deciders` are immutable (see `ignore_delete_decider_events` rule), so the rule is suspended for the duration of this migration only.
+DROP RULE IF EXISTS ignore_delete_decider_events ON deciders;
+
+DELETE
+FROM deciders
+WHERE decider = 'Restaurant'
+  AND event IN ('RestaurantOrderPlaced', 'RestaurantOrderNotPlaced')
+  AND NOT EXISTS(SELECT 1 FROM events WHERE events.decider = deciders.decider AND events.event = deciders.event);
+
+CREATE OR REPLACE RULE ignore_delete_decider_events AS ON DELETE TO deciders
+    DO INSTEAD NOTHING;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS correlation_index;
+ALTER TABLE events
+    DROP COLUMN IF EXISTS "metadata";
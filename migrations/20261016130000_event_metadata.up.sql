@@ -0,0 +1,6 @@
+-- Metadata of the command causing this event (correlation ID, causation ID, actor, timestamp, ...) in JSON format
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS "metadata" JSONB NOT NULL DEFAULT '{}';
+
+-- Trace all the events of a single flow (e.g. PlaceOrder -> OrderPlaced -> CreateOrder -> OrderCreated)
+CREATE INDEX IF NOT EXISTS correlation_index ON events (("metadata" ->> 'correlation_id'));
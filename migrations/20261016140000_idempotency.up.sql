@@ -0,0 +1,17 @@
+-- Idempotency keys - the outcome of the command handling, remembered by the client supplied key (per decider)
+CREATE TABLE IF NOT EXISTS idempotency_keys
+(
+    -- decider name/type
+    "decider"    TEXT                                   NOT NULL,
+    -- client supplied idempotency key (`Idempotency-Key` or `X-Command-Id` header)
+    "key"        TEXT                                   NOT NULL,
+    -- the command in JSON format - a repeated key must carry the same command
+    "command"    JSONB                                  NOT NULL,
+    -- the events produced by the command in JSON format; null while the command is being handled
+    "response"   JSONB                                  NULL,
+    -- the timestamp of the key insertion. The key is forgotten after the retention window
+    "created_at" TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    PRIMARY KEY ("decider", "key")
+);
+
+CREATE INDEX IF NOT EXISTS idempotency_keys_created_at_index ON idempotency_keys ("created_at");
@@ -0,0 +1,24 @@
+--  SIDE EFFECT: after appending a new event (with new decider_id), the lock is upserted, and every view is notified on the `events` channel (payload: view name)
+--  Notifications are delivered on commit, and the same view is notified only once per transaction
+CREATE OR REPLACE FUNCTION on_insert_on_events() RETURNS trigger AS
+'
+    BEGIN
+
+        INSERT INTO locks
+        SELECT t1.view        AS view,
+               NEW.decider_id AS decider_id,
+               NEW.offset     AS offset,
+               0              AS last_offset,
+               NOW()          AS locked_until,
+               NEW.final      AS offset_final
+        FROM views AS t1
+        ON CONFLICT ON CONSTRAINT "locks_pkey" DO UPDATE SET "offset"     = NEW."offset",
+                                                             offset_final = NEW.final;
+
+        PERFORM pg_notify(''events'', t1.view)
+        FROM views AS t1;
+
+        RETURN NEW;
+    END;
+'
+    LANGUAGE plpgsql;
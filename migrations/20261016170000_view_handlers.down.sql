@@ -0,0 +1,2 @@
+ALTER TABLE views
+    DROP COLUMN IF EXISTS "handler";
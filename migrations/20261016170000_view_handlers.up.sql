@@ -0,0 +1,21 @@
+-- Every view is streamed to the event handler (known to the application) of this name
+ALTER TABLE views
+    ADD COLUMN IF NOT EXISTS "handler" TEXT;
+
+-- Updating the views must not re-upsert the locks (`on_insert_or_update_on_views`), as it would reset the streaming progress
+ALTER TABLE views
+    DISABLE TRIGGER t_on_insert_or_update_on_views;
+
+UPDATE views
+SET handler = 'projection'
+WHERE view = 'view';
+
+UPDATE views
+SET handler = view
+WHERE handler IS NULL;
+
+ALTER TABLE views
+    ENABLE TRIGGER t_on_insert_or_update_on_views;
+
+ALTER TABLE views
+    ALTER COLUMN "handler" SET NOT NULL;
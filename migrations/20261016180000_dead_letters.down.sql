@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS dead_letters_status_index;
+DROP TABLE IF EXISTS dead_letters;
+ALTER TABLE locks
+    DROP COLUMN IF EXISTS "retries";
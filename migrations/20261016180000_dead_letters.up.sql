@@ -0,0 +1,35 @@
+-- Number of consecutive failures of the first unprocessed event in the stream - drives the exponential backoff (`locked_until`)
+ALTER TABLE locks
+    ADD COLUMN IF NOT EXISTS "retries" INTEGER DEFAULT 0 NOT NULL;
+
+-- Dead letters - the events the view failed to process too many times, skipped so that the rest of the decider stream can be processed
+CREATE TABLE IF NOT EXISTS dead_letters
+(
+    -- view identifier/name
+    "view"       TEXT                                   NOT NULL,
+    -- decider name/type
+    "decider"    TEXT                                   NOT NULL,
+    -- business identifier for the decider
+    "decider_id" TEXT                                   NOT NULL,
+    -- event name/type
+    "event"      TEXT                                   NOT NULL,
+    -- event ID
+    "event_id"   UUID                                   NOT NULL,
+    -- offset of the event
+    "offset"     BIGINT                                 NOT NULL,
+    -- the last error of the event processing
+    "error"      TEXT                                   NOT NULL,
+    -- number of the failed attempts to process the event
+    "retries"    INTEGER                                NOT NULL,
+    -- `Failed` - parked, or `Retry` - to be processed once again by the view
+    "status"     TEXT                                   NOT NULL,
+    -- the timestamp of the dead letter insertion
+    "created_at" TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    -- the timestamp of the dead letter update
+    "updated_at" TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    PRIMARY KEY ("view", "event_id"),
+    FOREIGN KEY ("view") REFERENCES views ("view") ON DELETE CASCADE,
+    FOREIGN KEY ("event_id") REFERENCES events ("event_id")
+);
+
+CREATE INDEX IF NOT EXISTS dead_letters_status_index ON dead_letters ("view", "status", "offset");
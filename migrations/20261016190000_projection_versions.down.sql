@@ -0,0 +1,26 @@
+DELETE
+FROM orders
+WHERE view NOT IN (SELECT view FROM active_projection);
+DELETE
+FROM restaurants
+WHERE view NOT IN (SELECT view FROM active_projection);
+
+DROP TABLE IF EXISTS active_projection;
+
+ALTER TABLE orders
+    DROP CONSTRAINT IF EXISTS orders_view_fkey;
+ALTER TABLE orders
+    DROP CONSTRAINT IF EXISTS orders_pkey;
+ALTER TABLE orders
+    ADD CONSTRAINT orders_pkey PRIMARY KEY ("id");
+ALTER TABLE orders
+    DROP COLUMN IF EXISTS "view";
+
+ALTER TABLE restaurants
+    DROP CONSTRAINT IF EXISTS restaurants_view_fkey;
+ALTER TABLE restaurants
+    DROP CONSTRAINT IF EXISTS restaurants_pkey;
+ALTER TABLE restaurants
+    ADD CONSTRAINT restaurants_pkey PRIMARY KEY ("id");
+ALTER TABLE restaurants
+    DROP COLUMN IF EXISTS "view";
@@ -0,0 +1,48 @@
+-- Every projection view (version) projects into its own rows of the `orders` and `restaurants` tables
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS "view" TEXT DEFAULT 'view' NOT NULL;
+ALTER TABLE orders
+    ALTER COLUMN "view" DROP DEFAULT;
+ALTER TABLE orders
+    DROP CONSTRAINT IF EXISTS orders_pkey;
+ALTER TABLE orders
+    ADD CONSTRAINT orders_pkey PRIMARY KEY ("view", "id");
+ALTER TABLE orders
+    ADD CONSTRAINT orders_view_fkey FOREIGN KEY ("view") REFERENCES views ("view") ON DELETE CASCADE;
+
+ALTER TABLE restaurants
+    ADD COLUMN IF NOT EXISTS "view" TEXT DEFAULT 'view' NOT NULL;
+ALTER TABLE restaurants
+    ALTER COLUMN "view" DROP DEFAULT;
+ALTER TABLE restaurants
+    DROP CONSTRAINT IF EXISTS restaurants_pkey;
+ALTER TABLE restaurants
+    ADD CONSTRAINT restaurants_pkey PRIMARY KEY ("view", "id");
+ALTER TABLE restaurants
+    ADD CONSTRAINT restaurants_view_fkey FOREIGN KEY ("view") REFERENCES views ("view") ON DELETE CASCADE;
+
+-- The projection view (version) serving the queries - a single row, switched atomically once the new version caught up with the event log
+CREATE TABLE IF NOT EXISTS active_projection
+(
+    -- always true - there is a single row only
+    "id"          BOOLEAN                  DEFAULT TRUE  NOT NULL CHECK ("id"),
+    -- view identifier/name
+    "view"        TEXT                                   NOT NULL,
+    -- the timestamp of the switch
+    "switched_at" TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL,
+    PRIMARY KEY ("id"),
+    FOREIGN KEY ("view") REFERENCES views ("view")
+);
+
+INSERT INTO active_projection (view)
+SELECT view
+FROM views
+WHERE view = 'view'
+ON CONFLICT DO NOTHING;
+
+-- The other projection views shared the rows of the `view` so far - they replay the full event log into their own rows
+UPDATE locks
+SET last_offset  = 0,
+    retries      = 0,
+    locked_until = NOW()
+WHERE view IN (SELECT view FROM views WHERE handler = 'projection' AND view <> 'view');
@@ -0,0 +1 @@
+DROP FUNCTION IF EXISTS stream_events_batch(TEXT, INTEGER, DOUBLE PRECISION);
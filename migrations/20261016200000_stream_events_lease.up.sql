@@ -0,0 +1,36 @@
+-- ##### API for streaming events in batches, with the configurable lease ######
+-- Lease the first unlocked decider stream with the unprocessed events for `v_lease_secs` seconds, and return up to `v_limit` of its events, ordered by offset
+CREATE OR REPLACE FUNCTION stream_events_batch(v_view_name TEXT, v_limit INTEGER, v_lease_secs DOUBLE PRECISION)
+    RETURNS SETOF events AS
+'
+    DECLARE
+        v_last_offset BIGINT;
+        v_decider_id  TEXT;
+    BEGIN
+        -- Check if there are events with a greater id than the last_offset and acquire lock on views table/row for the first decider_id/stream you can find
+        SELECT decider_id,
+               last_offset
+        INTO v_decider_id, v_last_offset
+        FROM locks
+        WHERE view = v_view_name
+          AND locked_until < NOW() -- locked = false
+          AND last_offset < "offset"
+        ORDER BY "offset"
+        LIMIT 1 FOR UPDATE SKIP LOCKED;
+
+        -- Update views locked status to true
+        UPDATE locks
+        SET locked_until = NOW() + make_interval(secs => v_lease_secs) -- locked = true, until the lease expires
+        WHERE view = v_view_name
+          AND locked_until < NOW() -- locked = false
+          AND decider_id = v_decider_id;
+
+        -- Return the batch of events that have not been processed yet
+        RETURN QUERY SELECT *
+                     FROM events
+                     WHERE decider_id = v_decider_id
+                       AND "offset" > v_last_offset
+                     ORDER BY "offset"
+                     LIMIT v_limit;
+    END;
+' LANGUAGE plpgsql;
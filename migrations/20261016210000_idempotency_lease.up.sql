@@ -0,0 +1,4 @@
+-- The lease of the idempotency key reservation - the key without the response is reclaimed by the repeated command once the lease expires:
+-- the handling of the command was abandoned (the request was dropped, the process crashed), or its outcome was not recorded
+ALTER TABLE idempotency_keys
+    ADD COLUMN IF NOT EXISTS "reserved_until" TIMESTAMP WITH TIME ZONE DEFAULT NOW() NOT NULL;
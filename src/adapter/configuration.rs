@@ -0,0 +1,430 @@
+use std::env::var;
+use std::time::Duration;
+
+use config::{Config, Environment, File};
+use serde::Deserialize;
+
+use crate::adapter::event_stream::dead_letter::DeadLetterPolicy;
+use crate::adapter::event_stream::worker::WorkerPoolConfig;
+use crate::application::error::AppError;
+use crate::application::retry::RetryPolicy;
+
+/// Path of the configuration file - environment variable. `config.toml` is loaded if it exists, by default
+pub const CONFIG_FILE: &str = "FMODEL_CONFIG";
+/// Default path of the configuration file
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+/// Prefix of the environment variables overriding the configuration, e.g. `FMODEL__HTTP__PORT=8080` overrides `http.port`
+const ENV_PREFIX: &str = "FMODEL";
+/// Separator of the prefix and the (nested) keys of the environment variables overriding the configuration
+const ENV_SEPARATOR: &str = "__";
+
+/// Database URL environment variable
+pub const DATABASE_URL: &str = "DATABASE_URL";
+/// Maximum number of command handling attempts on optimistic locking conflicts - environment variable
+pub const COMMAND_RETRY_MAX_ATTEMPTS: &str = "COMMAND_RETRY_MAX_ATTEMPTS";
+/// Delay (in milliseconds) before the first command handling retry - environment variable
+pub const COMMAND_RETRY_BACKOFF_MS: &str = "COMMAND_RETRY_BACKOFF_MS";
+/// How long (in seconds) the idempotency keys of the commands are remembered - environment variable
+pub const IDEMPOTENCY_RETENTION_SECS: &str = "IDEMPOTENCY_RETENTION_SECS";
+/// How long (in seconds) the idempotency key is reserved for the command being handled - environment variable
+pub const IDEMPOTENCY_LEASE_SECS: &str = "IDEMPOTENCY_LEASE_SECS";
+/// Maximum number of events of a single decider stream fetched and processed at once by the stream workers - environment variable
+pub const EVENT_STREAM_BATCH_SIZE: &str = "EVENT_STREAM_BATCH_SIZE";
+/// Number of concurrent workers streaming the events to every projection view - environment variable
+pub const VIEW_CONCURRENCY: &str = "VIEW_CONCURRENCY";
+/// Number of concurrent workers streaming the events to every saga view - environment variable
+pub const SAGA_CONCURRENCY: &str = "SAGA_CONCURRENCY";
+/// Maximum number of failed attempts to process the event, before it is moved to the dead letters - environment variable
+pub const DEAD_LETTER_MAX_RETRIES: &str = "DEAD_LETTER_MAX_RETRIES";
+/// Delay (in milliseconds) before the first retry of the failed event, doubled on every next retry - environment variable
+pub const DEAD_LETTER_BACKOFF_MS: &str = "DEAD_LETTER_BACKOFF_MS";
+
+/// The standalone environment variables, and the configuration keys they override - kept for the backward compatibility
+const ENV_OVERRIDES: [(&str, &str); 10] = [
+    (DATABASE_URL, "database.url"),
+    (COMMAND_RETRY_MAX_ATTEMPTS, "commands.retry_max_attempts"),
+    (COMMAND_RETRY_BACKOFF_MS, "commands.retry_backoff_ms"),
+    (
+        IDEMPOTENCY_RETENTION_SECS,
+        "commands.idempotency_retention_secs",
+    ),
+    (IDEMPOTENCY_LEASE_SECS, "commands.idempotency_lease_secs"),
+    (EVENT_STREAM_BATCH_SIZE, "workers.batch_size"),
+    (VIEW_CONCURRENCY, "workers.view_concurrency"),
+    (SAGA_CONCURRENCY, "workers.saga_concurrency"),
+    (DEAD_LETTER_MAX_RETRIES, "workers.dead_letter_max_retries"),
+    (DEAD_LETTER_BACKOFF_MS, "workers.dead_letter_backoff_ms"),
+];
+
+/// Application configuration - the configuration file, overridden by the environment variables
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    /// HTTP server
+    pub http: HttpConfig,
+    /// Database connection pool
+    pub database: DatabaseConfig,
+    /// Cross-origin resource sharing
+    pub cors: CorsConfig,
+    /// Event stream workers
+    pub workers: WorkersConfig,
+    /// Command handling
+    pub commands: CommandsConfig,
+    /// Logging
+    pub logging: LoggingConfig,
+    /// Health checks
+    pub health: HealthConfig,
+}
+
+/// HTTP server configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// The address the server binds to
+    pub host: String,
+    /// The port the server listens on
+    pub port: u16,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            host: "127.0.0.1".to_string(),
+            port: 8000,
+        }
+    }
+}
+
+/// Database connection pool configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Database URL - required
+    pub url: String,
+    /// Maximum number of connections of the pool - every worker pool holds one more for the event listener
+    pub max_connections: u32,
+    /// Minimum number of idle connections of the pool
+    pub min_connections: u32,
+    /// How long (in milliseconds) to wait for a connection of the pool
+    pub acquire_timeout_ms: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: String::new(),
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// How long to wait for a connection of the pool
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_millis(self.acquire_timeout_ms)
+    }
+}
+
+/// Cross-origin resource sharing configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API
+    pub allowed_origins: Vec<String>,
+    /// HTTP methods allowed to the origins
+    pub allowed_methods: Vec<String>,
+    /// Whether the credentials (cookies, authorization headers) are allowed
+    pub supports_credentials: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: vec!["http://localhost:8000".to_string()],
+            allowed_methods: ["GET", "POST", "PATCH", "DELETE"]
+                .map(str::to_string)
+                .to_vec(),
+            supports_credentials: true,
+        }
+    }
+}
+
+/// Event stream workers configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkersConfig {
+    /// Number of concurrent workers streaming the events to every projection view
+    pub view_concurrency: usize,
+    /// Number of concurrent workers streaming the events to every saga view
+    pub saga_concurrency: usize,
+    /// Maximum number of events of a single decider stream fetched and processed at once
+    pub batch_size: i32,
+    /// How long (in milliseconds) the decider stream is leased to the worker - the stream is handed over to another worker once the lease expires
+    pub lease_duration_ms: u64,
+    /// Maximum number of failed attempts to process the event, before it is moved to the dead letters
+    pub dead_letter_max_retries: i32,
+    /// Delay (in milliseconds) before the first retry of the failed event, doubled on every next retry
+    pub dead_letter_backoff_ms: u64,
+}
+
+impl Default for WorkersConfig {
+    fn default() -> Self {
+        let (worker_pool_config, dead_letter_policy) =
+            (WorkerPoolConfig::default(), DeadLetterPolicy::default());
+        WorkersConfig {
+            view_concurrency: worker_pool_config.concurrency,
+            saga_concurrency: worker_pool_config.concurrency,
+            batch_size: 100,
+            lease_duration_ms: 5 * 60 * 1000,
+            dead_letter_max_retries: dead_letter_policy.max_retries,
+            dead_letter_backoff_ms: dead_letter_policy.backoff.as_millis() as u64,
+        }
+    }
+}
+
+impl WorkersConfig {
+    /// How long the decider stream is leased to the worker
+    pub fn lease_duration(&self) -> Duration {
+        Duration::from_millis(self.lease_duration_ms)
+    }
+
+    /// Retry policy of the events the views failed to process, before they are moved to the dead letters
+    pub fn dead_letter_policy(&self) -> DeadLetterPolicy {
+        DeadLetterPolicy {
+            max_retries: self.dead_letter_max_retries,
+            backoff: Duration::from_millis(self.dead_letter_backoff_ms),
+        }
+    }
+}
+
+/// Command handling configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommandsConfig {
+    /// Maximum number of command handling attempts on optimistic locking conflicts
+    pub retry_max_attempts: u32,
+    /// Delay (in milliseconds) before the first command handling retry, doubled on every next retry
+    pub retry_backoff_ms: u64,
+    /// How long (in seconds) the idempotency keys of the commands are remembered
+    pub idempotency_retention_secs: u64,
+    /// How long (in seconds) the idempotency key is reserved for the command being handled, before the repeated command can reclaim it
+    pub idempotency_lease_secs: u64,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        let retry_policy = RetryPolicy::default();
+        CommandsConfig {
+            retry_max_attempts: retry_policy.max_attempts,
+            retry_backoff_ms: retry_policy.backoff.as_millis() as u64,
+            idempotency_retention_secs: 24 * 60 * 60,
+            idempotency_lease_secs: 60,
+        }
+    }
+}
+
+impl CommandsConfig {
+    /// Retry policy for the command handling on optimistic locking conflicts
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.retry_max_attempts,
+            backoff: Duration::from_millis(self.retry_backoff_ms),
+        }
+    }
+
+    /// How long the idempotency keys of the commands are remembered
+    pub fn idempotency_retention(&self) -> Duration {
+        Duration::from_secs(self.idempotency_retention_secs)
+    }
+
+    /// How long the idempotency key is reserved for the command being handled
+    pub fn idempotency_lease(&self) -> Duration {
+        Duration::from_secs(self.idempotency_lease_secs)
+    }
+}
+
+/// Logging format
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// JSON lines - for the log aggregators
+    Json,
+}
+
+/// Logging configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Logging format
+    pub format: LogFormat,
+    /// Logging filter (directives), e.g. `info,sqlx=warn` - the `RUST_LOG` environment variable takes precedence
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            format: LogFormat::default(),
+            filter: "info".to_string(),
+        }
+    }
+}
+
+/// Health checks configuration
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    /// Maximum age (in seconds) of the oldest event not processed yet by the view, before the application is not ready
+    pub max_lag_secs: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        HealthConfig { max_lag_secs: 60 }
+    }
+}
+
+impl AppConfig {
+    /// Load the configuration - the defaults, overridden by the configuration file, overridden by the environment variables - and validate it
+    pub fn load() -> Result<AppConfig, AppError> {
+        // The configuration file is optional, unless it is set explicitly
+        let (file, required) = match var(CONFIG_FILE) {
+            Ok(file) => (file, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+        let mut builder = Config::builder()
+            .add_source(File::with_name(&file).required(required))
+            .add_source(
+                Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator(ENV_SEPARATOR)
+                    .separator(ENV_SEPARATOR)
+                    .try_parsing(true)
+                    .list_separator(",")
+                    .with_list_parse_key("cors.allowed_origins")
+                    .with_list_parse_key("cors.allowed_methods"),
+            );
+        for (env, key) in ENV_OVERRIDES {
+            builder = builder
+                .set_override_option(key, var(env).ok())
+                .map_err(configuration_error)?;
+        }
+        let config: AppConfig = builder
+            .build()
+            .and_then(Config::try_deserialize)
+            .map_err(configuration_error)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validate the configuration - all the violations are reported at once
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut violations = Vec::new();
+        if self.http.host.trim().is_empty() {
+            violations.push("http.host must not be blank".to_string());
+        }
+        if self.database.url.trim().is_empty() {
+            violations.push(format!("database.url must be set (or {DATABASE_URL})"));
+        }
+        if self.database.max_connections == 0 {
+            violations.push("database.max_connections must be positive".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            violations.push(
+                "database.min_connections must not exceed database.max_connections".to_string(),
+            );
+        }
+        if self.database.acquire_timeout_ms == 0 {
+            violations.push("database.acquire_timeout_ms must be positive".to_string());
+        }
+        for origin in &self.cors.allowed_origins {
+            if !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                violations.push(format!(
+                    "cors.allowed_origins: '{origin}' must be an http(s) origin"
+                ));
+            }
+        }
+        for method in &self.cors.allowed_methods {
+            if actix_web::http::Method::from_bytes(method.as_bytes()).is_err() {
+                violations.push(format!(
+                    "cors.allowed_methods: '{method}' is not an HTTP method"
+                ));
+            }
+        }
+        if self.workers.view_concurrency == 0 {
+            violations.push("workers.view_concurrency must be positive".to_string());
+        }
+        if self.workers.saga_concurrency == 0 {
+            violations.push("workers.saga_concurrency must be positive".to_string());
+        }
+        if self.workers.batch_size <= 0 {
+            violations.push("workers.batch_size must be positive".to_string());
+        }
+        if self.workers.lease_duration_ms < 1000 {
+            violations.push("workers.lease_duration_ms must be at least 1000".to_string());
+        }
+        if self.workers.dead_letter_max_retries <= 0 {
+            violations.push("workers.dead_letter_max_retries must be positive".to_string());
+        }
+        if self.commands.retry_max_attempts == 0 {
+            violations.push("commands.retry_max_attempts must be positive".to_string());
+        }
+        if self.commands.idempotency_lease_secs == 0 {
+            violations.push("commands.idempotency_lease_secs must be positive".to_string());
+        }
+        if self.health.max_lag_secs == 0 {
+            violations.push("health.max_lag_secs must be positive".to_string());
+        }
+        if tracing_subscriber::EnvFilter::try_new(&self.logging.filter).is_err() {
+            violations.push(format!(
+                "logging.filter: '{}' is not a valid filter",
+                self.logging.filter
+            ));
+        }
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(AppError::Validation(format!(
+                "Invalid configuration: {}",
+                violations.join("; ")
+            ))),
+        }
+    }
+}
+
+/// Map the configuration (parsing) error
+fn configuration_error(error: config::ConfigError) -> AppError {
+    AppError::Validation(format!("Invalid configuration: {error}"))
+}
+
+#[cfg(test)]
+mod configuration_tests {
+    use crate::adapter::configuration::AppConfig;
+    use crate::application::error::AppError;
+
+    #[test]
+    fn default_configuration_with_database_url_is_valid() {
+        let mut config = AppConfig::default();
+        config.database.url = "postgres://localhost:5432/fmodel".to_string();
+
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn all_violations_are_reported() {
+        let mut config = AppConfig::default();
+        config.workers.view_concurrency = 0;
+        config.cors.allowed_origins = vec!["localhost".to_string()];
+
+        let Err(AppError::Validation(message)) = config.validate() else {
+            panic!("configuration should be invalid");
+        };
+        assert!(message.contains("database.url"));
+        assert!(message.contains("workers.view_concurrency"));
+        assert!(message.contains("cors.allowed_origins: 'localhost'"));
+    }
+}
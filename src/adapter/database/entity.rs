@@ -17,6 +17,7 @@ pub struct EventEntity {
     pub r#final: bool,
     pub created_at: Option<DateTime<Utc>>,
     pub offset: i64,
+    pub metadata: Value,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -29,6 +30,18 @@ pub struct NewEventEntity {
     pub command_id: Option<Uuid>,
     pub previous_id: Option<Uuid>,
     pub r#final: bool,
+    pub metadata: Value,
+}
+
+/// DB ENTITY: Idempotency keys - the outcome of the command handling, remembered by the client supplied key
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct IdempotencyKeyEntity {
+    pub decider: String,
+    pub key: String,
+    pub command: Value,
+    pub response: Option<Value>,
+    pub created_at: DateTime<Utc>,
+    pub reserved_until: DateTime<Utc>,
 }
 
 /// DB ENTITY: Registered deciders and the respectful events that these deciders can publish (decider can publish and/or source its own state from these event types only)
@@ -46,6 +59,41 @@ pub struct ViewEntity {
     pub start_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub handler: String,
+}
+
+/// DB ENTITY: Progress of the view - how many events of all the decider streams it processed (acked or skipped)
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct ViewProgressEntity {
+    pub view: String,
+    pub processed_events: i64,
+    pub total_events: i64,
+}
+
+/// DB ENTITY: Lag of the view - how far behind the head of the event log its decider streams are
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct ViewLagEntity {
+    pub view: String,
+    pub handler: String,
+    pub streams_behind: i64,
+    pub events_behind: i64,
+    pub offset_lag: i64,
+    pub leased_streams: i64,
+    pub oldest_unprocessed_event_at: Option<DateTime<Utc>>,
+    pub oldest_unprocessed_event_age_secs: Option<f64>,
+}
+
+/// DB ENTITY: Lag of the decider stream of the view - leased (locked) and/or behind the head of the stream
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct StreamLagEntity {
+    pub view: String,
+    pub decider_id: String,
+    pub offset: i64,
+    pub last_offset: i64,
+    pub locked_until: DateTime<Utc>,
+    pub retries: i32,
+    pub events_behind: i64,
+    pub oldest_unprocessed_event_at: Option<DateTime<Utc>>,
 }
 
 /// DB ENTITY: Locks are used to prevent concurrent processing of the same decider events ont query side
@@ -59,6 +107,23 @@ pub struct LockEntity {
     pub offset_final: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub retries: i32,
+}
+
+/// DB ENTITY: Dead letters - the events the view failed to process too many times, skipped in the decider stream
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct DeadLetterEntity {
+    pub view: String,
+    pub decider: String,
+    pub decider_id: String,
+    pub event: String,
+    pub event_id: Uuid,
+    pub offset: i64,
+    pub error: String,
+    pub retries: i32,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 /// DB ENTITY: Order view state
@@ -66,6 +131,7 @@ pub struct LockEntity {
 pub struct OrderEntity {
     pub id: String,
     pub data: Value,
+    pub view: String,
 }
 
 /// DB ENTITY: Restaurant view state
@@ -73,4 +139,13 @@ pub struct OrderEntity {
 pub struct RestaurantEntity {
     pub id: String,
     pub data: Value,
+    pub view: String,
+}
+
+/// DB ENTITY: The projection view (version) serving the queries
+#[derive(Serialize, Deserialize, Debug, FromRow)]
+pub struct ActiveProjectionEntity {
+    pub id: bool,
+    pub view: String,
+    pub switched_at: DateTime<Utc>,
 }
@@ -1,34 +1,40 @@
-use std::fmt;
+use crate::application::error::AppError;
 
-use serde::{Deserialize, Serialize};
+/// SQLSTATE raised by Postgres on a `UNIQUE` constraint violation
+const UNIQUE_VIOLATION: &str = "23505";
+/// SQLSTATE raised by Postgres on a `RAISE EXCEPTION` within the event store triggers
+const RAISE_EXCEPTION: &str = "P0001";
+/// The event store constraints violated by the concurrent appends to the same decider stream - `previous_id` and `offset` are UNIQUE
+const EVENT_STORE_CONSTRAINTS: [&str; 2] = ["events_previous_id_key", "events_pkey"];
 
-/// Error message to be returned to the client
-#[derive(Serialize, Deserialize)]
-pub struct ErrorMessage {
-    pub message: String,
-}
-
-/// Implement Display for ErrorMessage
-impl fmt::Display for ErrorMessage {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
-    }
-}
-
-/// Implement Debug for ErrorMessage
-impl fmt::Debug for ErrorMessage {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "ErrorMessage: {}", self.message)
-    }
-}
-
-// Implementing `From<E>` for your error type allows automatic conversion from any error type `E` that implements `std::error::Error` into your custom error type. This is very useful when your code interacts with multiple libraries or modules that produce different error types.
+// Implementing `From<E>` for your error type allows automatic conversion from the library error types into your custom error type.
 // It enables the use of the `?` operator seamlessly, converting various error types into your unified error type without boilerplate `map_err()` calls.
 // Check `queries.rs` for usage example.
-impl<E: std::error::Error> From<E> for ErrorMessage {
-    fn from(value: E) -> Self {
-        ErrorMessage {
-            message: value.to_string(),
+impl From<sqlx::Error> for AppError {
+    fn from(value: sqlx::Error) -> Self {
+        match &value {
+            sqlx::Error::RowNotFound => AppError::NotFound(value.to_string()),
+            sqlx::Error::Database(error) => match error.code().as_deref() {
+                // `previous_id` is UNIQUE - two commands computed the same previous event concurrently.
+                // Any other UNIQUE violation is not an optimistic locking conflict, and retrying will not help
+                Some(UNIQUE_VIOLATION)
+                    if error.constraint().is_some_and(|constraint| {
+                        EVENT_STORE_CONSTRAINTS.contains(&constraint)
+                    }) =>
+                {
+                    AppError::Conflict(error.message().to_string())
+                }
+                // `check_final_event_for_decider` trigger
+                Some(RAISE_EXCEPTION) if error.message().contains("final") => {
+                    AppError::StreamClosed(error.message().to_string())
+                }
+                // `check_first_event_for_decider` and `check_previous_id_in_same_decider` triggers
+                Some(RAISE_EXCEPTION) if error.message().contains("previous_id") => {
+                    AppError::Conflict(error.message().to_string())
+                }
+                _ => AppError::Infrastructure(error.message().to_string()),
+            },
+            _ => AppError::Infrastructure(value.to_string()),
         }
     }
 }
@@ -1,3 +1,9 @@
+use sqlx::migrate::Migrator;
+
 pub mod entity;
 pub mod error;
 pub mod queries;
+pub mod registry;
+
+/// Database migrations - embedded from the `migrations` directory
+pub static MIGRATOR: Migrator = sqlx::migrate!();
@@ -1,59 +1,127 @@
 use actix_web::web;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
 use web::Data;
 
 use crate::adapter::database::entity::{
-    DeciderEventEntity, EventEntity, LockEntity, NewEventEntity, OrderEntity, RestaurantEntity,
-    ViewEntity,
+    ActiveProjectionEntity, DeadLetterEntity, DeciderEventEntity, EventEntity,
+    IdempotencyKeyEntity, LockEntity, NewEventEntity, OrderEntity, RestaurantEntity,
+    StreamLagEntity, ViewEntity, ViewLagEntity, ViewProgressEntity,
 };
-use crate::adapter::database::error::ErrorMessage;
+use crate::application::error::AppError;
 use crate::Database;
 
 // ############################### COMMAND SIDE ###############################
 
 /// DB: Register the type of event(s) that this `decider` is able to publish/store
 /// Event can not be inserted into `event` table without the matching event being registered previously. It is controlled by the 'Foreign Key' constraint on the `event` table
-#[allow(dead_code)]
 pub async fn register_decider(
     event: &String,
     decider: &String,
-    app: &Data<Database>,
-) -> Result<DeciderEventEntity, ErrorMessage> {
+    app: &Database,
+) -> Result<DeciderEventEntity, AppError> {
     Ok(sqlx::query_as!(
         DeciderEventEntity,
         "INSERT INTO deciders (decider, event) VALUES ($1, $2) RETURNING *;",
-        event,
-        decider
+        decider,
+        event
     )
     .fetch_one(&app.db)
     .await?)
 }
 
+/// DB: Get all the registered deciders and the respectful events
+pub async fn list_deciders(app: &Database) -> Result<Vec<DeciderEventEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        DeciderEventEntity,
+        "SELECT * FROM deciders ORDER BY decider, event"
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: Check the connection
+pub async fn ping(app: &Database) -> Result<(), AppError> {
+    sqlx::query("SELECT 1").execute(&app.db).await?;
+    Ok(())
+}
+
+/// DB: List the versions of the successfully applied migrations
+pub async fn list_applied_migrations(app: &Database) -> Result<Vec<i64>, AppError> {
+    Ok(
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+            .fetch_all(&app.db)
+            .await?,
+    )
+}
+
 /// DB: Get events by `decider_id`
 /// Used by the `Decider/Entity` to get list of events from where it can source its own state
 #[allow(dead_code)]
 pub async fn list_events(
     decider_id: &String,
     app: &Database,
-) -> Result<Vec<EventEntity>, ErrorMessage> {
+) -> Result<Vec<EventEntity>, AppError> {
     Ok(sqlx::query_as!(
         EventEntity,
-        "SELECT * FROM events WHERE decider_id = $1 ORDER BY events.offset",
+        "SELECT event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata
+            FROM events WHERE decider_id = $1 ORDER BY events.offset",
         decider_id
     )
     .fetch_all(&app.db)
     .await?)
 }
 
+/// DB: Get a page of events of the `decider` stream by `decider_id`, starting from the `from_offset` (inclusive)
+/// Used by the event history query to explain how the `Decider/Entity` reached its current state
+pub async fn list_decider_events(
+    decider: &String,
+    decider_id: &String,
+    from_offset: &i64,
+    limit: &i64,
+    app: &Database,
+) -> Result<Vec<EventEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        EventEntity,
+        "SELECT event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata
+            FROM events WHERE decider = $1 AND decider_id = $2 AND events.offset >= $3 ORDER BY events.offset LIMIT $4",
+        decider,
+        decider_id,
+        from_offset,
+        limit
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: Get all the events of a single flow by `correlation_id`, across all the deciders
+/// Used by the event history query to trace the flow end to end (e.g. PlaceOrder -> OrderPlaced -> CreateOrder -> OrderCreated)
+pub async fn list_correlated_events(
+    correlation_id: &String,
+    app: &Database,
+) -> Result<Vec<EventEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        EventEntity,
+        "SELECT event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata
+            FROM events WHERE metadata ->> 'correlation_id' = $1 ORDER BY events.offset",
+        correlation_id
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
 /// DB: Get the latest event by `decider_id`
 /// Used by the `Decider/Entity` to get the latest event from where it can get the latest version of its own state / use it for optimistic locking
 #[allow(dead_code)]
 pub async fn get_latest_event(
     decider_id: &String,
     app: &Database,
-) -> Result<Option<EventEntity>, ErrorMessage> {
+) -> Result<Option<EventEntity>, AppError> {
     Ok(sqlx::query_as!(
         EventEntity,
-        "SELECT * FROM events WHERE decider_id = $1 ORDER BY events.offset DESC LIMIT 1",
+        "SELECT event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata
+            FROM events WHERE decider_id = $1 ORDER BY events.offset DESC LIMIT 1",
         decider_id
     )
     .fetch_optional(&app.db)
@@ -62,15 +130,12 @@ pub async fn get_latest_event(
 
 /// DB: Append/Insert new 'event'
 #[allow(dead_code)]
-pub async fn append_event(
-    event: &NewEventEntity,
-    app: &Database,
-) -> Result<EventEntity, ErrorMessage> {
+pub async fn append_event(event: &NewEventEntity, app: &Database) -> Result<EventEntity, AppError> {
     Ok(sqlx::query_as!(
         EventEntity,
-        "INSERT INTO events (event, event_id, decider, decider_id, data, command_id, previous_id, final)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING *",
+        "INSERT INTO events (event, event_id, decider, decider_id, data, command_id, previous_id, final, metadata)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata",
         event.event,
         event.event_id,
         event.decider,
@@ -78,7 +143,8 @@ pub async fn append_event(
         event.data,
         event.command_id,
         event.previous_id,
-        event.r#final
+        event.r#final,
+        event.metadata
     )
         .fetch_one(&app.db)
         .await?)
@@ -88,20 +154,18 @@ pub async fn append_event(
 pub async fn append_events(
     events: &[NewEventEntity],
     app: &Database, // Ensure `Database` contains `sqlx::Pool<sqlx::Postgres>`
-) -> Result<Vec<EventEntity>, ErrorMessage> {
+) -> Result<Vec<EventEntity>, AppError> {
     // Start a new transaction
-    let mut tx = app.db.begin().await.map_err(|e| ErrorMessage {
-        message: e.to_string(),
-    })?;
+    let mut tx = app.db.begin().await?;
 
     let mut appended_events = Vec::new();
 
     for event in events {
         let appended_event = sqlx::query_as!(
             EventEntity,
-            "INSERT INTO events (event, event_id, decider, decider_id, data, command_id, previous_id, final)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING *",
+            "INSERT INTO events (event, event_id, decider, decider_id, data, command_id, previous_id, final, metadata)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata",
             event.event,
             event.event_id,
             event.decider,
@@ -109,7 +173,8 @@ pub async fn append_events(
             event.data,
             event.command_id,
             event.previous_id,
-            event.r#final
+            event.r#final,
+            event.metadata
         )
         .fetch_one(&mut *tx)
         .await?;
@@ -123,36 +188,301 @@ pub async fn append_events(
     Ok(appended_events)
 }
 
+/// DB: Forget all the idempotency keys older than `retention_secs`
+pub async fn purge_idempotency_keys(retention_secs: &f64, app: &Database) -> Result<u64, AppError> {
+    Ok(sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(secs => $1)",
+        retention_secs
+    )
+    .execute(&app.db)
+    .await?
+    .rows_affected())
+}
+
+/// DB: Reserve the idempotency `key` for the `decider` command, leased for `lease_secs` - `None` if the key is already reserved.
+/// The key is reclaimed if its reservation lease expired without the response (by the same command only), or if it is older than `retention_secs`
+pub async fn reserve_idempotency_key(
+    decider: &String,
+    key: &String,
+    command: &Value,
+    lease_secs: &f64,
+    retention_secs: &f64,
+    app: &Database,
+) -> Result<Option<IdempotencyKeyEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        IdempotencyKeyEntity,
+        "INSERT INTO idempotency_keys (decider, key, command, reserved_until)
+            VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))
+            ON CONFLICT ON CONSTRAINT idempotency_keys_pkey DO UPDATE SET command        = EXCLUDED.command,
+                                                                          response       = NULL,
+                                                                          created_at     = NOW(),
+                                                                          reserved_until = EXCLUDED.reserved_until
+            WHERE (idempotency_keys.response IS NULL
+                       AND idempotency_keys.reserved_until < NOW()
+                       AND idempotency_keys.command = EXCLUDED.command)
+               OR idempotency_keys.created_at < NOW() - make_interval(secs => $5)
+            RETURNING *",
+        decider,
+        key,
+        command,
+        lease_secs,
+        retention_secs
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Get the idempotency `key` for the `decider` command
+pub async fn get_idempotency_key(
+    decider: &String,
+    key: &String,
+    app: &Database,
+) -> Result<Option<IdempotencyKeyEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        IdempotencyKeyEntity,
+        "SELECT * FROM idempotency_keys WHERE decider = $1 AND key = $2",
+        decider,
+        key
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Remember the `response` (produced events) of the command reserved by the idempotency `key`
+pub async fn complete_idempotency_key(
+    decider: &String,
+    key: &String,
+    response: &Value,
+    app: &Database,
+) -> Result<IdempotencyKeyEntity, AppError> {
+    Ok(sqlx::query_as!(
+        IdempotencyKeyEntity,
+        "UPDATE idempotency_keys SET response = $3 WHERE decider = $1 AND key = $2 RETURNING *",
+        decider,
+        key,
+        response
+    )
+    .fetch_one(&app.db)
+    .await?)
+}
+
+/// DB: Release the idempotency `key` of the command that failed, so that it can be retried
+pub async fn release_idempotency_key(
+    decider: &String,
+    key: &String,
+    app: &Database,
+) -> Result<u64, AppError> {
+    Ok(sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE decider = $1 AND key = $2 AND response IS NULL",
+        decider,
+        key
+    )
+    .execute(&app.db)
+    .await?
+    .rows_affected())
+}
+
 // ############################### QUERY SIDE ###############################
 
-/// DB: Register a new view
-#[allow(dead_code)]
+/// DB: Register a new view - the events are streamed to it from the `start_at` point in time (`NOW()` by default)
 pub async fn register_view(
     view: &String,
+    handler: &String,
     pooling_delay: &i64,
+    start_at: &Option<DateTime<Utc>>,
     app: &Data<Database>,
-) -> Result<ViewEntity, ErrorMessage> {
-    Ok(sqlx::query_as!(
+) -> Result<ViewEntity, AppError> {
+    sqlx::query_as!(
         ViewEntity,
-        "INSERT INTO views (view, pooling_delay)
-                VALUES ($1, $2)
+        "INSERT INTO views (view, handler, pooling_delay, start_at)
+                VALUES ($1, $2, $3, COALESCE($4, NOW()))
+                ON CONFLICT ON CONSTRAINT views_pkey DO NOTHING
                 RETURNING *;",
         view,
-        pooling_delay
+        handler,
+        pooling_delay,
+        *start_at
+    )
+    .fetch_optional(&app.db)
+    .await?
+    .ok_or_else(|| AppError::Conflict(format!("View {view} is already registered")))
+}
+
+/// DB: List all the registered views
+pub async fn list_views(app: &Database) -> Result<Vec<ViewEntity>, AppError> {
+    Ok(
+        sqlx::query_as!(ViewEntity, "SELECT * FROM views ORDER BY view")
+            .fetch_all(&app.db)
+            .await?,
+    )
+}
+
+/// DB: Get the view by name
+pub async fn get_view(view: &String, app: &Database) -> Result<Option<ViewEntity>, AppError> {
+    Ok(
+        sqlx::query_as!(ViewEntity, "SELECT * FROM views WHERE view = $1", view)
+            .fetch_optional(&app.db)
+            .await?,
+    )
+}
+
+/// DB: Get the progress of the view - the events at or below the `last_offset` of its locks are processed
+pub async fn get_view_progress(
+    view: &String,
+    app: &Database,
+) -> Result<ViewProgressEntity, AppError> {
+    Ok(sqlx::query_as!(
+        ViewProgressEntity,
+        "SELECT $1::TEXT AS \"view!\",
+                COUNT(e.\"offset\") FILTER (WHERE e.\"offset\" <= l.last_offset) AS \"processed_events!\",
+                COUNT(e.\"offset\") AS \"total_events!\"
+            FROM locks AS l
+            JOIN events AS e ON e.decider_id = l.decider_id
+            WHERE l.view = $1",
+        view
     )
     .fetch_one(&app.db)
     .await?)
 }
 
-/// DB: Stream events from the `event` table to the materialized view
+/// DB: Get the lag of the views (or the `view` only) - the decider streams and events not processed yet, and the leased streams
+/// `offset_lag` is the sum of `offset - last_offset` of the streams behind - the offsets are global (all deciders), so it is an upper bound of `events_behind`
+pub async fn list_view_lags(
+    view: &Option<String>,
+    app: &Database,
+) -> Result<Vec<ViewLagEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        ViewLagEntity,
+        "WITH stream_lags AS (SELECT l.view,
+                                     l.decider_id,
+                                     l.\"offset\" - l.last_offset AS offset_lag,
+                                     l.locked_until,
+                                     COUNT(e.\"offset\") AS events_behind,
+                                     MIN(e.created_at) AS oldest_unprocessed_event_at
+                              FROM locks AS l
+                              LEFT JOIN events AS e ON e.decider_id = l.decider_id AND e.\"offset\" > l.last_offset
+                              GROUP BY l.view, l.decider_id)
+         SELECT v.view AS \"view!\",
+                v.handler AS \"handler!\",
+                COUNT(s.decider_id) FILTER (WHERE s.events_behind > 0) AS \"streams_behind!\",
+                COALESCE(SUM(s.events_behind), 0)::BIGINT AS \"events_behind!\",
+                COALESCE(SUM(s.offset_lag) FILTER (WHERE s.events_behind > 0), 0)::BIGINT AS \"offset_lag!\",
+                COUNT(s.decider_id) FILTER (WHERE s.locked_until > NOW()) AS \"leased_streams!\",
+                MIN(s.oldest_unprocessed_event_at) AS oldest_unprocessed_event_at,
+                EXTRACT(EPOCH FROM NOW() - MIN(s.oldest_unprocessed_event_at))::FLOAT8 AS oldest_unprocessed_event_age_secs
+            FROM views AS v
+            LEFT JOIN stream_lags AS s ON s.view = v.view
+            WHERE $1::TEXT IS NULL OR v.view = $1
+            GROUP BY v.view, v.handler
+            ORDER BY v.view",
+        view.as_deref()
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: List the decider streams of the views (or the `view` only) currently leased by the workers - processing, or waiting for the retry
+pub async fn list_leased_streams(
+    view: &Option<String>,
+    app: &Database,
+) -> Result<Vec<StreamLagEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        StreamLagEntity,
+        "SELECT l.view,
+                l.decider_id,
+                l.\"offset\",
+                l.last_offset,
+                l.locked_until,
+                l.retries,
+                COUNT(e.\"offset\") AS \"events_behind!\",
+                MIN(e.created_at) AS oldest_unprocessed_event_at
+            FROM locks AS l
+            LEFT JOIN events AS e ON e.decider_id = l.decider_id AND e.\"offset\" > l.last_offset
+            WHERE l.locked_until > NOW() AND ($1::TEXT IS NULL OR l.view = $1)
+            GROUP BY l.view, l.decider_id
+            ORDER BY l.view, l.locked_until",
+        view.as_deref()
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: List up to `limit` decider streams of the views (or the `view` only) behind the head of the stream - the longest waiting first
+pub async fn list_lagging_streams(
+    view: &Option<String>,
+    limit: &i64,
+    app: &Database,
+) -> Result<Vec<StreamLagEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        StreamLagEntity,
+        "SELECT l.view,
+                l.decider_id,
+                l.\"offset\",
+                l.last_offset,
+                l.locked_until,
+                l.retries,
+                COUNT(e.\"offset\") AS \"events_behind!\",
+                MIN(e.created_at) AS oldest_unprocessed_event_at
+            FROM locks AS l
+            JOIN events AS e ON e.decider_id = l.decider_id AND e.\"offset\" > l.last_offset
+            WHERE $1::TEXT IS NULL OR l.view = $1
+            GROUP BY l.view, l.decider_id
+            ORDER BY MIN(e.created_at), l.view
+            LIMIT $2",
+        view.as_deref(),
+        limit
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: Reset the projection view to replay the full event log - its rows of the projection tables are deleted, and its locks and dead letters are reset.
+/// The workers of the view must be stopped meanwhile.
+pub async fn reset_projection_view(view: &String, app: &Database) -> Result<u64, AppError> {
+    let mut tx = app.db.begin().await?;
+
+    sqlx::query!("DELETE FROM orders WHERE view = $1", view)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!("DELETE FROM restaurants WHERE view = $1", view)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!("DELETE FROM dead_letters WHERE view = $1", view)
+        .execute(&mut *tx)
+        .await?;
+
+    let reset_locks = sqlx::query!(
+        "UPDATE locks
+            SET locked_until = NOW(), -- locked = false,
+                last_offset = 0,
+                retries = 0
+            WHERE view = $1",
+        view
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    Ok(reset_locks)
+}
+
+/// DB: Stream the batch of up to `limit` ordered events of the single decider stream, leased for the view/event-handler for `lease_secs` seconds
 pub async fn stream_events(
     view: &String,
+    limit: &i32,
+    lease_secs: &f64,
     app: &Database,
-) -> Result<Option<EventEntity>, ErrorMessage> {
+) -> Result<Vec<EventEntity>, AppError> {
     Ok(
-        sqlx::query_as::<_, EventEntity>("SELECT * FROM stream_events($1)")
+        sqlx::query_as::<_, EventEntity>("SELECT * FROM stream_events_batch($1, $2, $3)")
             .bind(view)
-            .fetch_optional(&app.db)
+            .bind(limit)
+            .bind(lease_secs)
+            .fetch_all(&app.db)
             .await?,
     )
 }
@@ -163,12 +493,13 @@ pub async fn ack_event(
     view: &String,
     decider_id: &String,
     app: &Database,
-) -> Result<LockEntity, ErrorMessage> {
+) -> Result<LockEntity, AppError> {
     Ok(sqlx::query_as!(
         LockEntity,
         "UPDATE locks
             SET locked_until = NOW(), -- locked = false,
-                last_offset = $1
+                last_offset = $1,
+                retries = 0
             WHERE view = $2
             AND decider_id = $3
             RETURNING *;",
@@ -180,98 +511,343 @@ pub async fn ack_event(
     .await?)
 }
 
-/// DB: Nack that event was not processed successfully
+/// DB: Nack that event was not processed successfully - the stream is locked for `backoff_secs * 2^retries` before the next attempt
 pub async fn nack_event(
     view: &String,
     decider_id: &String,
+    backoff_secs: &f64,
     app: &Database,
-) -> Result<LockEntity, ErrorMessage> {
+) -> Result<LockEntity, AppError> {
     Ok(sqlx::query_as!(
         LockEntity,
         "UPDATE locks
-            SET locked_until = NOW() -- locked = false
+            SET locked_until = NOW() + make_interval(secs => $3 * power(2, retries)), -- locked = true, until the next attempt
+                retries = retries + 1
             WHERE view = $1
             AND decider_id = $2
             RETURNING *;",
         view,
+        decider_id,
+        backoff_secs
+    )
+    .fetch_one(&app.db)
+    .await?)
+}
+
+/// DB: Move the event that failed too many times to the dead letters, and ack it - so the rest of the stream can be processed
+pub async fn dead_letter_event(
+    view: &String,
+    event: &EventEntity,
+    error: &String,
+    retries: &i32,
+    app: &Database,
+) -> Result<DeadLetterEntity, AppError> {
+    let mut tx = app.db.begin().await?;
+
+    let dead_letter = sqlx::query_as!(
+        DeadLetterEntity,
+        "INSERT INTO dead_letters (view, decider, decider_id, event, event_id, \"offset\", error, retries, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'Failed')
+            ON CONFLICT ON CONSTRAINT dead_letters_pkey DO UPDATE SET error      = EXCLUDED.error,
+                                                                     retries    = EXCLUDED.retries,
+                                                                     status     = EXCLUDED.status,
+                                                                     updated_at = NOW()
+            RETURNING *",
+        view,
+        event.decider,
+        event.decider_id,
+        event.event,
+        event.event_id,
+        event.offset,
+        error,
+        retries
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE locks
+            SET locked_until = NOW(), -- locked = false,
+                last_offset = $1,
+                retries = 0
+            WHERE view = $2
+            AND decider_id = $3",
+        event.offset,
+        view,
+        event.decider_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(dead_letter)
+}
+
+/// DB: List the dead letters of the `view` (all the views if `None`)
+pub async fn list_dead_letters(
+    view: &Option<String>,
+    app: &Database,
+) -> Result<Vec<DeadLetterEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        DeadLetterEntity,
+        "SELECT * FROM dead_letters WHERE $1::TEXT IS NULL OR view = $1 ORDER BY view, \"offset\"",
+        view.as_deref()
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: Get the dead letter of the `view` by `event_id`
+pub async fn get_dead_letter(
+    view: &String,
+    event_id: &Uuid,
+    app: &Database,
+) -> Result<Option<DeadLetterEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        DeadLetterEntity,
+        "SELECT * FROM dead_letters WHERE view = $1 AND event_id = $2",
+        view,
+        event_id
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Get the offset of the last event of the decider stream processed (or skipped) by the `view` - `None` if the stream is not streamed to the view
+pub async fn get_last_offset(
+    view: &String,
+    decider_id: &String,
+    app: &Database,
+) -> Result<Option<i64>, AppError> {
+    Ok(sqlx::query_scalar!(
+        "SELECT last_offset FROM locks WHERE view = $1 AND decider_id = $2",
+        view,
         decider_id
     )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Mark the dead letter to be processed once again by the view - `None` if it does not exist
+pub async fn retry_dead_letter(
+    view: &String,
+    event_id: &Uuid,
+    app: &Database,
+) -> Result<Option<DeadLetterEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        DeadLetterEntity,
+        "UPDATE dead_letters SET status = 'Retry', updated_at = NOW() WHERE view = $1 AND event_id = $2 RETURNING *",
+        view,
+        event_id
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Lease the next dead letter marked for the retry by the view - it is marked as failed again, until processed successfully
+pub async fn lease_dead_letter(
+    view: &String,
+    app: &Database,
+) -> Result<Option<DeadLetterEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        DeadLetterEntity,
+        "UPDATE dead_letters
+            SET status = 'Failed', retries = retries + 1, updated_at = NOW()
+            WHERE view = $1
+            AND event_id = (SELECT event_id FROM dead_letters
+                            WHERE view = $1 AND status = 'Retry'
+                            ORDER BY \"offset\"
+                            LIMIT 1 FOR UPDATE SKIP LOCKED)
+            RETURNING *",
+        view
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Record the error of the failed dead letter retry
+pub async fn fail_dead_letter(
+    view: &String,
+    event_id: &Uuid,
+    error: &String,
+    app: &Database,
+) -> Result<DeadLetterEntity, AppError> {
+    Ok(sqlx::query_as!(
+        DeadLetterEntity,
+        "UPDATE dead_letters SET error = $3, updated_at = NOW() WHERE view = $1 AND event_id = $2 RETURNING *",
+        view,
+        event_id,
+        error
+    )
     .fetch_one(&app.db)
     .await?)
 }
 
-/// DB: Get the Order view state by `id`
-pub async fn get_order(id: &String, app: &Database) -> Result<Option<OrderEntity>, ErrorMessage> {
-    Ok(
-        sqlx::query_as!(OrderEntity, "SELECT * FROM orders WHERE id = $1", id)
-            .fetch_optional(&app.db)
-            .await?,
+/// DB: Discard the dead letter - the event is not processed by the view ever - `None` if it does not exist
+pub async fn discard_dead_letter(
+    view: &String,
+    event_id: &Uuid,
+    app: &Database,
+) -> Result<Option<DeadLetterEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        DeadLetterEntity,
+        "DELETE FROM dead_letters WHERE view = $1 AND event_id = $2 RETURNING *",
+        view,
+        event_id
     )
+    .fetch_optional(&app.db)
+    .await?)
 }
 
-/// DB: Get all the Order view states
-pub async fn get_all_orders(app: &Database) -> Result<Vec<OrderEntity>, ErrorMessage> {
-    Ok(sqlx::query_as!(OrderEntity, "SELECT * FROM orders",)
-        .fetch_all(&app.db)
-        .await?)
+/// DB: Get the event by `event_id`
+pub async fn get_event(event_id: &Uuid, app: &Database) -> Result<EventEntity, AppError> {
+    Ok(sqlx::query_as!(
+        EventEntity,
+        "SELECT event, event_id, decider, decider_id, data, command_id, previous_id, final, created_at, \"offset\", metadata
+            FROM events WHERE event_id = $1",
+        event_id
+    )
+    .fetch_one(&app.db)
+    .await?)
 }
 
-/// DB: Insert/Update the Order view state
-pub async fn upsert_order(
-    order: &OrderEntity,
+/// DB: Get the Order view state by `id` - from the active projection view
+pub async fn get_order(id: &String, app: &Database) -> Result<Option<OrderEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        OrderEntity,
+        "SELECT o.* FROM orders AS o JOIN active_projection AS a ON a.view = o.view WHERE o.id = $1",
+        id
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Get all the Order view states - from the active projection view
+pub async fn get_all_orders(app: &Database) -> Result<Vec<OrderEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        OrderEntity,
+        "SELECT o.* FROM orders AS o JOIN active_projection AS a ON a.view = o.view",
+    )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: Get the Order view state by `id` - from the projection `view`
+pub async fn get_order_in_view(
+    id: &String,
+    view: &String,
     app: &Database,
-) -> Result<OrderEntity, ErrorMessage> {
+) -> Result<Option<OrderEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        OrderEntity,
+        "SELECT * FROM orders WHERE view = $1 AND id = $2",
+        view,
+        id
+    )
+    .fetch_optional(&app.db)
+    .await?)
+}
+
+/// DB: Insert/Update the Order view state
+pub async fn upsert_order(order: &OrderEntity, app: &Database) -> Result<OrderEntity, AppError> {
     Ok(sqlx::query_as!(
         OrderEntity,
-        "INSERT INTO orders (id, data)
-            VALUES ($1, $2)
+        "INSERT INTO orders (id, data, view)
+            VALUES ($1, $2, $3)
          ON CONFLICT ON CONSTRAINT orders_pkey
          DO UPDATE SET data = EXCLUDED.data
             RETURNING *",
         order.id,
         order.data,
+        order.view,
     )
     .fetch_one(&app.db)
     .await?)
 }
 
-/// DB: Get the Restaurant view state by `id`
+/// DB: Get the Restaurant view state by `id` - from the active projection view
 pub async fn get_restaurant(
     id: &String,
     app: &Database,
-) -> Result<Option<RestaurantEntity>, ErrorMessage> {
+) -> Result<Option<RestaurantEntity>, AppError> {
     Ok(sqlx::query_as!(
         RestaurantEntity,
-        "SELECT * FROM restaurants WHERE id = $1",
+        "SELECT r.* FROM restaurants AS r JOIN active_projection AS a ON a.view = r.view WHERE r.id = $1",
         id
     )
     .fetch_optional(&app.db)
     .await?)
 }
 
-/// DB: Get all the Restaurant view states
-pub async fn get_all_restaurants(app: &Database) -> Result<Vec<RestaurantEntity>, ErrorMessage> {
-    Ok(
-        sqlx::query_as!(RestaurantEntity, "SELECT * FROM restaurants",)
-            .fetch_all(&app.db)
-            .await?,
+/// DB: Get all the Restaurant view states - from the active projection view
+pub async fn get_all_restaurants(app: &Database) -> Result<Vec<RestaurantEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        RestaurantEntity,
+        "SELECT r.* FROM restaurants AS r JOIN active_projection AS a ON a.view = r.view",
     )
+    .fetch_all(&app.db)
+    .await?)
+}
+
+/// DB: Get the Restaurant view state by `id` - from the projection `view`
+pub async fn get_restaurant_in_view(
+    id: &String,
+    view: &String,
+    app: &Database,
+) -> Result<Option<RestaurantEntity>, AppError> {
+    Ok(sqlx::query_as!(
+        RestaurantEntity,
+        "SELECT * FROM restaurants WHERE view = $1 AND id = $2",
+        view,
+        id
+    )
+    .fetch_optional(&app.db)
+    .await?)
 }
 
 /// DB: Insert/Update the Restaurant view state
 pub async fn upsert_restaurant(
     restaurant: &RestaurantEntity,
     app: &Database,
-) -> Result<RestaurantEntity, ErrorMessage> {
+) -> Result<RestaurantEntity, AppError> {
     Ok(sqlx::query_as!(
         RestaurantEntity,
-        "INSERT INTO restaurants (id, data)
-            VALUES ($1, $2)
+        "INSERT INTO restaurants (id, data, view)
+            VALUES ($1, $2, $3)
          ON CONFLICT ON CONSTRAINT restaurants_pkey
          DO UPDATE SET data = EXCLUDED.data
          RETURNING *",
         restaurant.id,
         restaurant.data,
+        restaurant.view,
+    )
+    .fetch_one(&app.db)
+    .await?)
+}
+
+/// DB: Get the projection view (version) serving the queries
+pub async fn get_active_projection(app: &Database) -> Result<ActiveProjectionEntity, AppError> {
+    Ok(
+        sqlx::query_as!(ActiveProjectionEntity, "SELECT * FROM active_projection")
+            .fetch_one(&app.db)
+            .await?,
+    )
+}
+
+/// DB: Switch the queries to the projection `view` - atomically
+pub async fn activate_projection(
+    view: &String,
+    app: &Database,
+) -> Result<ActiveProjectionEntity, AppError> {
+    Ok(sqlx::query_as!(
+        ActiveProjectionEntity,
+        "INSERT INTO active_projection (view)
+            VALUES ($1)
+         ON CONFLICT ON CONSTRAINT active_projection_pkey
+         DO UPDATE SET view = EXCLUDED.view, switched_at = NOW()
+         RETURNING *",
+        view
     )
     .fetch_one(&app.db)
     .await?)
@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use crate::adapter::database::entity::DeciderEventEntity;
+use crate::adapter::database::queries::{list_deciders, register_decider};
+use crate::application::error::AppError;
+use crate::Database;
+
+/// Register the (decider, event) catalogue of the code in the event store.
+/// Missing pairs are registered, and the registration fails fast if the event store contains pairs that are unknown to the code.
+pub async fn register_deciders(
+    catalogue: &[(String, String)],
+    db: &Database,
+) -> Result<Vec<DeciderEventEntity>, AppError> {
+    let in_code: BTreeSet<(String, String)> = catalogue.iter().cloned().collect();
+    let in_db: BTreeSet<(String, String)> = list_deciders(db)
+        .await?
+        .into_iter()
+        .map(|entity| (entity.decider, entity.event))
+        .collect();
+
+    let unknown: Vec<String> = in_db
+        .difference(&in_code)
+        .map(|(decider, event)| format!("- {decider}/{event}"))
+        .collect();
+    if !unknown.is_empty() {
+        let missing: Vec<String> = in_code
+            .difference(&in_db)
+            .map(|(decider, event)| format!("+ {decider}/{event}"))
+            .collect();
+        return Err(AppError::Infrastructure(format!(
+            "Registered deciders do not match the code (- registered in the database only, + defined in the code only):\n{}",
+            [unknown, missing].concat().join("\n")
+        )));
+    }
+
+    let mut registered = Vec::new();
+    for (decider, event) in in_code.difference(&in_db) {
+        tracing::info!("Registering event {event} for decider {decider}");
+        registered.push(register_decider(event, decider, db).await?);
+    }
+    Ok(registered)
+}
+
+/// The (decider, event) pairs of the catalogue that are not registered in the event store yet
+pub async fn missing_deciders(
+    catalogue: &[(String, String)],
+    db: &Database,
+) -> Result<Vec<(String, String)>, AppError> {
+    let in_db: BTreeSet<(String, String)> = list_deciders(db)
+        .await?
+        .into_iter()
+        .map(|entity| (entity.decider, entity.event))
+        .collect();
+    Ok(catalogue
+        .iter()
+        .filter(|pair| !in_db.contains(*pair))
+        .cloned()
+        .collect())
+}
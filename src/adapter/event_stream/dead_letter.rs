@@ -0,0 +1,149 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tracing::{error, info, warn};
+
+use crate::adapter::database::entity::{DeadLetterEntity, EventEntity};
+use crate::adapter::database::queries::{
+    dead_letter_event, discard_dead_letter, fail_dead_letter, get_event, get_last_offset,
+    lease_dead_letter, nack_event,
+};
+use crate::adapter::metrics::METRICS;
+use crate::application::error::AppError;
+use crate::Database;
+
+/// Dead-letter policy - how many times, and how often the failed event is retried before it is moved to the dead letters
+#[derive(Clone, Debug)]
+pub struct DeadLetterPolicy {
+    /// Maximum number of failed attempts to process the event
+    pub max_retries: i32,
+    /// Delay before the first retry, doubled on every next retry
+    pub backoff: Duration,
+}
+
+impl Default for DeadLetterPolicy {
+    fn default() -> Self {
+        DeadLetterPolicy {
+            max_retries: 5,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Nack the event the `view` failed to process - it is retried with the exponential backoff, or moved to the dead letters once it failed `max_retries` times
+pub async fn nack_or_dead_letter(
+    view: &String,
+    event_entity: &EventEntity,
+    error: &AppError,
+    policy: &DeadLetterPolicy,
+    db: &Database,
+) -> Result<(), AppError> {
+    let lock = nack_event(
+        view,
+        &event_entity.decider_id,
+        &policy.backoff.as_secs_f64(),
+        db,
+    )
+    .await?;
+    METRICS.nacks.with_label_values(&[view]).inc();
+    if lock.retries >= policy.max_retries {
+        dead_letter_event(view, event_entity, &error.to_string(), &lock.retries, db).await?;
+        METRICS.dead_letters.with_label_values(&[view]).inc();
+        warn!(
+            "Event {} ({}) moved to the dead letters of the view {view} after {} failures: {error}",
+            event_entity.event, event_entity.event_id, lock.retries
+        );
+    }
+    Ok(())
+}
+
+/// Check that the dead letter can be retried in the stream order - the view skipped it, and has not processed any later event of its decider stream since (`last_offset`).
+/// Otherwise, retrying it would apply the old event over the newer state (e.g. `OrderCreated` over the delivered order) - the projection must be rebuilt instead.
+pub fn check_retry_in_order(
+    dead_letter: &DeadLetterEntity,
+    last_offset: Option<i64>,
+) -> Result<(), AppError> {
+    match last_offset {
+        Some(last_offset) if last_offset == dead_letter.offset => Ok(()),
+        _ => Err(AppError::Conflict(format!(
+            "Dead letter {} ({}) of the view {} can not be retried - the view has processed the later events of the decider stream {} already, rebuild the projection view instead",
+            dead_letter.event, dead_letter.event_id, dead_letter.view, dead_letter.decider_id
+        ))),
+    }
+}
+
+/// Process the next dead letter marked for the retry by the `view` with the `handle` - it is discarded once processed successfully.
+/// The dead letter is not processed (and is failed again) if the view has processed the later events of its decider stream meanwhile (check `check_retry_in_order`).
+/// Returns `true` if there was one to process.
+pub async fn process_dead_letter<F, Fut>(
+    view: &String,
+    handle: F,
+    db: &Database,
+) -> Result<bool, AppError>
+where
+    F: FnOnce(EventEntity) -> Fut,
+    Fut: Future<Output = Result<(), AppError>>,
+{
+    let Some(dead_letter) = lease_dead_letter(view, db).await? else {
+        return Ok(false);
+    };
+    let last_offset = get_last_offset(view, &dead_letter.decider_id, db).await?;
+    if let Err(error) = check_retry_in_order(&dead_letter, last_offset) {
+        warn!("{error}");
+        fail_dead_letter(view, &dead_letter.event_id, &error.to_string(), db).await?;
+        return Ok(true);
+    }
+    let event_entity = get_event(&dead_letter.event_id, db).await?;
+    match handle(event_entity).await {
+        Ok(_) => {
+            info!(
+                "Dead letter {} ({}) processed successfully by the view {view}",
+                dead_letter.event, dead_letter.event_id
+            );
+            discard_dead_letter(view, &dead_letter.event_id, db).await?;
+        }
+        Err(error) => {
+            error!(
+                "Dead letter {} ({}) failed again in the view {view}: {error}",
+                dead_letter.event, dead_letter.event_id
+            );
+            fail_dead_letter(view, &dead_letter.event_id, &error.to_string(), db).await?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+/// Tests for the dead letters
+mod dead_letter_tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use crate::adapter::database::entity::DeadLetterEntity;
+    use crate::adapter::event_stream::dead_letter::check_retry_in_order;
+
+    #[test]
+    fn check_retry_in_order_test() {
+        let dead_letter = DeadLetterEntity {
+            view: "view".to_string(),
+            decider: "Order".to_string(),
+            decider_id: "02f09a3f-1624-3b1d-8409-44eff7708207".to_string(),
+            event: "OrderCreated".to_string(),
+            event_id: Uuid::new_v4(),
+            offset: 10,
+            error: "Error".to_string(),
+            retries: 5,
+            status: "Retry".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // The view skipped the dead letter, and has not moved on since
+        assert!(check_retry_in_order(&dead_letter, Some(10)).is_ok());
+        // The view has processed the later events of the decider stream
+        assert!(check_retry_in_order(&dead_letter, Some(11)).is_err());
+        // The decider stream is being replayed (rebuilt), or is not streamed to the view at all
+        assert!(check_retry_in_order(&dead_letter, Some(0)).is_err());
+        assert!(check_retry_in_order(&dead_letter, None).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+use tokio::time::{sleep, timeout};
+use tracing::{debug, warn};
+
+use crate::application::error::AppError;
+use crate::Database;
+
+/// Channel the `on_insert_on_events` trigger notifies on - the payload is the name of the view
+pub const EVENTS_CHANNEL: &str = "events";
+
+/// Event listener - wakes the stream worker of the view when new events are appended
+pub struct EventListener {
+    listener: PgListener,
+    view: String,
+    /// How long to wait for the notification before polling anyway - covers the notifications missed while reconnecting
+    polling_interval: Duration,
+}
+
+/// EventListener - struct implementation
+impl EventListener {
+    /// Create a new EventListener, listening on the dedicated connection for the new events of the `view`
+    pub async fn connect(
+        view: &str,
+        polling_interval: Duration,
+        db: &Database,
+    ) -> Result<Self, AppError> {
+        let mut listener = PgListener::connect_with(&db.db).await?;
+        listener.listen(EVENTS_CHANNEL).await?;
+        Ok(EventListener {
+            listener,
+            view: view.to_string(),
+            polling_interval,
+        })
+    }
+
+    /// Wait until new events of the view are notified, or the polling interval elapses.
+    /// Returns immediately if the connection was lost - the notifications might have been missed, so the events should be polled.
+    pub async fn wait(&mut self) {
+        let notified = timeout(self.polling_interval, async {
+            loop {
+                match self.listener.try_recv().await {
+                    Ok(Some(notification)) if notification.payload() == self.view => {
+                        debug!("Notified on new events for the view {}", self.view);
+                        return;
+                    }
+                    Ok(Some(_)) => continue,
+                    Ok(None) => {
+                        warn!(
+                            "Event listener of the view {} lost the connection, reconnecting and polling",
+                            self.view
+                        );
+                        return;
+                    }
+                    Err(error) => {
+                        warn!(
+                            "Event listener of the view {} failed, polling: {}",
+                            self.view, error
+                        );
+                        sleep(self.polling_interval).await;
+                        return;
+                    }
+                }
+            }
+        })
+        .await;
+        if notified.is_err() {
+            debug!("No notification for the view {}, polling", self.view);
+        }
+    }
+}
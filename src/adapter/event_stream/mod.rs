@@ -1,2 +1,5 @@
+pub mod dead_letter;
+pub mod listener;
 pub mod saga_stream;
 pub mod view_stream;
+pub mod worker;
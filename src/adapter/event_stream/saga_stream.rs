@@ -1,66 +1,147 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info_span, warn, Instrument};
 
-use crate::adapter::database::error::ErrorMessage;
-use crate::adapter::database::queries::{ack_event, nack_event, stream_events};
-use crate::adapter::publisher::order_action_publisher::OrderActionPublisher;
-use crate::adapter::repository::event_repository::ToEvent;
-use crate::application::api::OrderSagaManager;
+use crate::adapter::database::entity::EventEntity;
+use crate::adapter::database::queries::{ack_event, stream_events};
+use crate::adapter::event_stream::dead_letter::{
+    nack_or_dead_letter, process_dead_letter, DeadLetterPolicy,
+};
+use crate::adapter::metrics::{outcome, METRICS};
+use crate::adapter::publisher::order_action_publisher::{self, OrderActionPublisher};
+use crate::adapter::publisher::restaurant_action_publisher::{self, RestaurantActionPublisher};
+use crate::adapter::repository::event_repository::{ToCommandMetadata, ToEvent};
+use crate::adapter::telemetry::follow_trace_context;
+use crate::application::api::{OrderSagaManager, RestaurantSagaManager};
+use crate::application::error::AppError;
+use crate::application::metadata::CommandMetadata;
 use crate::Database;
 
-/// Stream events to the saga manager - Simple implementation
+/// Name of the event handler reacting to the Restaurant events with the Order commands, and to the Order events with the compensating Restaurant commands - referred by the `views.handler`
+pub const HANDLER: &str = "saga";
+
+/// Stream events to the saga managers - Simple implementation
+/// The batch of up to `batch_size` events of a single decider stream is processed in order, and the last successful offset is acked once - the stream is leased for `lease_duration` meanwhile.
+/// The failed event is retried with the backoff, and moved to the dead letters eventually - check the `dead_letter_policy`.
+/// Returns `true` if the whole batch was processed successfully, so the next one might be waiting already
 pub async fn stream_events_to_saga(
     order_saga_manager: Arc<OrderSagaManager<'_, OrderActionPublisher<'_>>>,
+    restaurant_saga_manager: Arc<RestaurantSagaManager<'_, RestaurantActionPublisher<'_>>>,
+    view: &String,
+    batch_size: &i32,
+    lease_duration: &Duration,
+    dead_letter_policy: &DeadLetterPolicy,
     db: &Database,
-) -> Result<(), ErrorMessage> {
-    // Stream events from the `event` table to the saga manager of name "saga"
+) -> Result<bool, AppError> {
+    // Retry the dead letters first, if requested
+    let (order_saga_manager, restaurant_saga_manager) =
+        (&*order_saga_manager, &*restaurant_saga_manager);
+    if process_dead_letter(
+        view,
+        |event_entity| async move {
+            handle_event(order_saga_manager, restaurant_saga_manager, &event_entity).await
+        },
+        db,
+    )
+    .await?
+    {
+        return Ok(true);
+    }
+
+    // Stream events from the `event` table to the saga manager of name `view`
     // NOTE: Saga manager is also an event handler
-    match stream_events(&"saga".to_string(), db).await {
-        Ok(Some(event_entity)) => {
-            debug!("Processing Event in Saga: {event_entity:?}");
-            match event_entity.decider.as_str() {
-                "Restaurant" => match order_saga_manager.handle(&event_entity.to_event()?).await {
-                    Ok(_) => {
-                        debug!("Order Saga executed successfully");
-                        ack_event(
-                            &event_entity.offset,
-                            &"saga".to_string(),
-                            &event_entity.decider_id,
-                            db,
-                        )
-                        .await
-                        .map(drop)
-                    }
-                    Err(error) => {
-                        error!("Order Saga failed: {}", error.message);
-                        nack_event(&"saga".to_string(), &event_entity.decider_id, db)
-                            .await
-                            .map(drop)
-                    }
-                },
-                _ => {
-                    warn!("Unknown event type: {}", event_entity.event);
-                    ack_event(
-                        &event_entity.offset,
-                        &"saga".to_string(),
-                        &event_entity.decider_id,
-                        db,
-                    )
-                    .await
-                    .map(drop)
-                }
+    let started = Instant::now();
+    let event_entities = stream_events(view, batch_size, &lease_duration.as_secs_f64(), db).await;
+    METRICS
+        .stream_polling_latency
+        .with_label_values(&[view])
+        .observe(started.elapsed().as_secs_f64());
+    let event_entities = match event_entities {
+        Ok(event_entities) => event_entities,
+        Err(error) => {
+            error!("Error: {}", error);
+            return Err(error);
+        }
+    };
+    let Some(decider_id) = event_entities
+        .first()
+        .map(|entity| entity.decider_id.clone())
+    else {
+        debug!("No events to process in SAGA, continue with the next iteration");
+        return Ok(false);
+    };
+
+    let mut last_offset = None;
+    let mut failure = None;
+    for event_entity in &event_entities {
+        debug!("Processing Event in Saga: {event_entity:?}");
+        match handle_event(order_saga_manager, restaurant_saga_manager, event_entity).await {
+            Ok(_) => {
+                debug!("Saga executed successfully");
+                last_offset = Some(event_entity.offset);
+            }
+            Err(error) => {
+                error!("Saga failed: {}", error);
+                failure = Some((event_entity, error));
+                break;
             }
         }
-        Ok(None) => {
-            debug!("No events to process in SAGA, continue with the next iteration");
-            Ok(())
+    }
+
+    match (last_offset, &failure) {
+        (Some(offset), _) => {
+            ack_event(&offset, view, &decider_id, db).await?;
         }
-        Err(error) => {
-            error!("Error: {}", error.message);
-            Err(ErrorMessage {
-                message: error.message,
-            })
+        (None, Some((event_entity, error))) => {
+            nack_or_dead_letter(view, event_entity, error, dead_letter_policy, db).await?;
         }
+        (None, None) => {}
     }
+    Ok(failure.is_none())
+}
+
+/// React to the Restaurant event with the Order commands, or to the Order event with the compensating Restaurant commands - within the flow (correlation) of the command that caused the event
+async fn handle_event(
+    order_saga_manager: &OrderSagaManager<'_, OrderActionPublisher<'_>>,
+    restaurant_saga_manager: &RestaurantSagaManager<'_, RestaurantActionPublisher<'_>>,
+    event_entity: &EventEntity,
+) -> Result<(), AppError> {
+    let actor = match event_entity.decider.as_str() {
+        "Restaurant" => order_action_publisher::ACTOR,
+        "Order" => restaurant_action_publisher::ACTOR,
+        _ => {
+            warn!("Unknown event type: {}", event_entity.event);
+            return Ok(());
+        }
+    };
+    let metadata = event_entity
+        .to_command_metadata()
+        .unwrap_or_else(|| CommandMetadata::new(actor));
+    // The reaction continues the trace of the command that caused the event
+    let span =
+        info_span!("saga.react", event = %event_entity.event, event_id = %event_entity.event_id);
+    follow_trace_context(&span, &metadata.trace_context);
+    let reaction = async {
+        match event_entity.decider.as_str() {
+            "Restaurant" => order_saga_manager
+                .handle(&event_entity.to_event()?)
+                .await
+                .map(drop),
+            _ => restaurant_saga_manager
+                .handle(&event_entity.to_event()?)
+                .await
+                .map(drop),
+        }
+    };
+    let result = metadata
+        .caused_by(event_entity.event_id, actor)
+        .scope(reaction)
+        .instrument(span)
+        .await;
+    METRICS
+        .saga_reactions
+        .with_label_values(&[&event_entity.event, outcome(&result)])
+        .inc();
+    result
 }
@@ -1,100 +1,132 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::adapter::database::error::ErrorMessage;
-use crate::adapter::database::queries::{ack_event, nack_event, stream_events};
+use crate::adapter::database::entity::EventEntity;
+use crate::adapter::database::queries::{ack_event, stream_events};
+use crate::adapter::event_stream::dead_letter::{
+    nack_or_dead_letter, process_dead_letter, DeadLetterPolicy,
+};
+use crate::adapter::metrics::METRICS;
 use crate::adapter::repository::event_repository::ToEvent;
 use crate::adapter::repository::order_view_state_repository::OrderViewStateRepository;
 use crate::adapter::repository::restaurant_view_state_repository::RestaurantViewStateRepository;
 use crate::application::api::{OrderMaterializedView, RestaurantMaterializedView};
+use crate::application::error::AppError;
 use crate::Database;
 use tracing::{debug, error, warn};
 
+/// Name of the event handler projecting the events into the Restaurant and Order materialized views - referred by the `views.handler`
+pub const HANDLER: &str = "projection";
+
 /// Stream events to the materialized view - Simple implementation
+/// The batch of up to `batch_size` events of a single decider stream is processed in order, and the last successful offset is acked once - the stream is leased for `lease_duration` meanwhile.
+/// The failed event is retried with the backoff, and moved to the dead letters eventually - check the `dead_letter_policy`.
+/// Returns `true` if the whole batch was processed successfully, so the next one might be waiting already
 pub async fn stream_events_to_view(
     restaurant_materialized_view: Arc<
         RestaurantMaterializedView<'_, RestaurantViewStateRepository>,
     >,
     order_materialized_view: Arc<OrderMaterializedView<'_, OrderViewStateRepository>>,
+    view: &String,
+    batch_size: &i32,
+    lease_duration: &Duration,
+    dead_letter_policy: &DeadLetterPolicy,
     db: &Database,
-) -> Result<(), ErrorMessage> {
-    // Stream events from the `event` table to the materialized view of name "view"
-    match stream_events(&"view".to_string(), db).await {
-        Ok(Some(event_entity)) => {
-            debug!("Processing Event: {event_entity:?}");
-            match event_entity.decider.as_str() {
-                "Restaurant" => {
-                    match restaurant_materialized_view
-                        .handle(&event_entity.to_event()?)
-                        .await
-                    {
-                        Ok(_) => {
-                            debug!("Restaurant materialized view updated successfully");
-                            ack_event(
-                                &event_entity.offset,
-                                &"view".to_string(),
-                                &event_entity.decider_id,
-                                db,
-                            )
-                            .await
-                            .map(drop)
-                        }
-                        Err(error) => {
-                            error!(
-                                "Restaurant materialized view update failed: {}",
-                                error.message
-                            );
-                            nack_event(&"view".to_string(), &event_entity.decider_id, db)
-                                .await
-                                .map(drop)
-                        }
-                    }
-                }
-                "Order" => {
-                    match order_materialized_view
-                        .handle(&event_entity.to_event()?)
-                        .await
-                    {
-                        Ok(_) => {
-                            debug!("Order materialized view updated successfully");
-                            ack_event(
-                                &event_entity.offset,
-                                &"view".to_string(),
-                                &event_entity.decider_id,
-                                db,
-                            )
-                            .await
-                            .map(drop)
-                        }
-                        Err(error) => {
-                            error!("Order materialized view update failed: {}", error.message);
-                            nack_event(&"view".to_string(), &event_entity.decider_id, db)
-                                .await
-                                .map(drop)
-                        }
-                    }
-                }
-                _ => {
-                    warn!("Unknown event type: {}", event_entity.event);
-                    ack_event(
-                        &event_entity.offset,
-                        &"view".to_string(),
-                        &event_entity.decider_id,
-                        db,
-                    )
-                    .await
-                    .map(drop)
-                }
+) -> Result<bool, AppError> {
+    // Retry the dead letters first, if requested
+    let (restaurant, order) = (&*restaurant_materialized_view, &*order_materialized_view);
+    if process_dead_letter(
+        view,
+        |event_entity| async move { handle_event(restaurant, order, &event_entity).await },
+        db,
+    )
+    .await?
+    {
+        return Ok(true);
+    }
+
+    // Stream events from the `event` table to the materialized view of name `view`
+    let started = Instant::now();
+    let event_entities = stream_events(view, batch_size, &lease_duration.as_secs_f64(), db).await;
+    METRICS
+        .stream_polling_latency
+        .with_label_values(&[view])
+        .observe(started.elapsed().as_secs_f64());
+    let event_entities = match event_entities {
+        Ok(event_entities) => event_entities,
+        Err(error) => {
+            error!("Error: {}", error);
+            return Err(error);
+        }
+    };
+    let Some(decider_id) = event_entities
+        .first()
+        .map(|entity| entity.decider_id.clone())
+    else {
+        debug!("No events to process, continue with the next iteration");
+        return Ok(false);
+    };
+
+    let mut last_offset = None;
+    let mut failure = None;
+    for event_entity in &event_entities {
+        debug!("Processing Event: {event_entity:?}");
+        match handle_event(
+            &restaurant_materialized_view,
+            &order_materialized_view,
+            event_entity,
+        )
+        .await
+        {
+            Ok(_) => {
+                debug!(
+                    "{} materialized view updated successfully",
+                    event_entity.decider
+                );
+                last_offset = Some(event_entity.offset);
+            }
+            Err(error) => {
+                error!(
+                    "{} materialized view update failed: {}",
+                    event_entity.decider, error
+                );
+                failure = Some((event_entity, error));
+                break;
             }
         }
-        Ok(None) => {
-            debug!("No events to process, continue with the next iteration");
-            Ok(())
+    }
+
+    match (last_offset, &failure) {
+        (Some(offset), _) => {
+            ack_event(&offset, view, &decider_id, db).await?;
         }
-        Err(error) => {
-            error!("Error: {}", error.message);
-            Err(ErrorMessage {
-                message: error.message,
-            })
+        (None, Some((event_entity, error))) => {
+            nack_or_dead_letter(view, event_entity, error, dead_letter_policy, db).await?;
+        }
+        (None, None) => {}
+    }
+    Ok(failure.is_none())
+}
+
+/// Project the event into the Restaurant or Order materialized view
+#[tracing::instrument(name = "projection.update", skip_all, fields(event = %event_entity.event, event_id = %event_entity.event_id))]
+async fn handle_event(
+    restaurant_materialized_view: &RestaurantMaterializedView<'_, RestaurantViewStateRepository>,
+    order_materialized_view: &OrderMaterializedView<'_, OrderViewStateRepository>,
+    event_entity: &EventEntity,
+) -> Result<(), AppError> {
+    match event_entity.decider.as_str() {
+        "Restaurant" => restaurant_materialized_view
+            .handle(&event_entity.to_event()?)
+            .await
+            .map(drop),
+        "Order" => order_materialized_view
+            .handle(&event_entity.to_event()?)
+            .await
+            .map(drop),
+        _ => {
+            warn!("Unknown event type: {}", event_entity.event);
+            Ok(())
         }
     }
 }
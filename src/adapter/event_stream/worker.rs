@@ -0,0 +1,719 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, Notify};
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio::time::{sleep, timeout, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::adapter::database::entity::ViewEntity;
+use crate::adapter::event_stream::listener::EventListener;
+use crate::application::error::AppError;
+use crate::Database;
+
+/// How long the stopped worker pool is waited for - the workers finish the batch in flight (check `run`), and are aborted if it takes longer
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Worker pool configuration - per view/saga
+#[derive(Clone, Debug)]
+pub struct WorkerPoolConfig {
+    /// Number of concurrent workers - every worker leases a different decider stream (`FOR UPDATE SKIP LOCKED`)
+    pub concurrency: usize,
+    /// How long the workers wait for the new events notification before polling anyway
+    pub polling_interval: Duration,
+    /// Delay before the first restart of the failed worker, doubled on every next consecutive failure
+    pub restart_backoff: Duration,
+    /// Maximum delay before the restart of the failed worker
+    pub max_restart_backoff: Duration,
+}
+
+impl Default for WorkerPoolConfig {
+    fn default() -> Self {
+        WorkerPoolConfig {
+            concurrency: 1,
+            polling_interval: Duration::from_secs(1),
+            restart_backoff: Duration::from_millis(100),
+            max_restart_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Worker state
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum WorkerState {
+    /// The worker is processing the events, or waiting for the new ones
+    Running,
+    /// The worker failed, and is waiting to be restarted
+    Restarting,
+    /// The worker is stopped - the application is shutting down
+    Stopped,
+}
+
+/// Worker status - reported by the health endpoint
+#[derive(Serialize, Clone, Debug)]
+pub struct WorkerStatus {
+    /// Name of the view/saga the worker is streaming the events to
+    pub view: String,
+    /// Index of the worker within the pool
+    pub worker: usize,
+    /// Current state of the worker
+    pub state: WorkerState,
+    /// Number of restarts of the worker
+    pub restarts: u32,
+    /// The error that caused the last restart
+    pub last_error: Option<String>,
+    /// When the worker processed the last batch of events successfully
+    pub last_processed_at: Option<DateTime<Utc>>,
+    /// When the worker status changed last time
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Worker registry - the statuses of all the stream workers
+#[derive(Clone, Default)]
+pub struct WorkerRegistry {
+    statuses: Arc<RwLock<BTreeMap<(String, usize), WorkerStatus>>>,
+}
+
+/// WorkerRegistry - struct implementation
+impl WorkerRegistry {
+    /// The statuses of all the workers, ordered by view and worker index
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        self.statuses
+            .read()
+            .map(|statuses| statuses.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Are all the workers running
+    pub fn is_healthy(&self) -> bool {
+        self.statuses()
+            .iter()
+            .all(|status| status.state == WorkerState::Running)
+    }
+
+    fn update(&self, view: &str, worker: usize, update: impl FnOnce(&mut WorkerStatus)) {
+        if let Ok(mut statuses) = self.statuses.write() {
+            let status = statuses
+                .entry((view.to_string(), worker))
+                .or_insert_with(|| WorkerStatus {
+                    view: view.to_string(),
+                    worker,
+                    state: WorkerState::Running,
+                    restarts: 0,
+                    last_error: None,
+                    last_processed_at: None,
+                    updated_at: Utc::now(),
+                });
+            update(status);
+            status.updated_at = Utc::now();
+        }
+    }
+}
+
+/// Worker pool command - handled by the task managing the worker pools (check `manage_worker_pools`)
+pub enum WorkerPoolCommand {
+    /// Start the worker pool of the view
+    Start(ViewEntity),
+    /// Stop the worker pool of the view, and reply once all its workers are stopped
+    Stop(String, oneshot::Sender<()>),
+}
+
+/// Worker pool manager - hands the views registered (or paused) at runtime over to the task managing their worker pools
+#[derive(Clone)]
+pub struct WorkerPoolManager {
+    /// Names of the event handlers known to the application
+    handlers: Vec<String>,
+    sender: UnboundedSender<WorkerPoolCommand>,
+}
+
+/// WorkerPoolManager - struct implementation
+impl WorkerPoolManager {
+    /// Create a new WorkerPoolManager for the known `handlers`, and the receiving end of the commands
+    pub fn new(handlers: &[&str]) -> (Self, UnboundedReceiver<WorkerPoolCommand>) {
+        let (sender, receiver) = unbounded_channel();
+        (
+            WorkerPoolManager {
+                handlers: handlers.iter().map(|handler| handler.to_string()).collect(),
+                sender,
+            },
+            receiver,
+        )
+    }
+
+    /// Check that the event `handler` is known to the application
+    pub fn validate(&self, handler: &str) -> Result<(), AppError> {
+        if self.handlers.iter().any(|known| known == handler) {
+            Ok(())
+        } else {
+            Err(AppError::Validation(format!(
+                "Unknown event handler '{handler}', expected one of: {}",
+                self.handlers.join(", ")
+            )))
+        }
+    }
+
+    /// Start the worker pool of the `view`
+    pub fn start(&self, view: ViewEntity) -> Result<(), AppError> {
+        self.send(WorkerPoolCommand::Start(view))
+    }
+
+    /// Stop the worker pool of the `view` - returns once all its workers are stopped, or aborted after `STOP_TIMEOUT`
+    pub async fn stop(&self, view: &str) -> Result<(), AppError> {
+        let (reply, stopped) = oneshot::channel();
+        self.send(WorkerPoolCommand::Stop(view.to_string(), reply))?;
+        stopped.await.map_err(|error| {
+            AppError::Infrastructure(format!(
+                "Worker pool of the view {view} can not be stopped: {error}"
+            ))
+        })
+    }
+
+    fn send(&self, command: WorkerPoolCommand) -> Result<(), AppError> {
+        self.sender
+            .send(command)
+            .map_err(|_| AppError::Infrastructure("Worker pools are shut down".to_string()))
+    }
+}
+
+/// Manage the worker pools of the views - start the pools of the `views`, then start/stop the pools on the `commands`, until the `cancellation`.
+/// `start` spawns the worker pool of the view, stopped by its own cancellation token (check `spawn_worker_pool`).
+pub async fn manage_worker_pools<S>(
+    views: Vec<ViewEntity>,
+    mut commands: UnboundedReceiver<WorkerPoolCommand>,
+    start: S,
+    cancellation: CancellationToken,
+) where
+    S: Fn(&ViewEntity, &CancellationToken) -> Vec<JoinHandle<()>>,
+{
+    let mut worker_pools: BTreeMap<String, (CancellationToken, Vec<JoinHandle<()>>)> =
+        BTreeMap::new();
+    let start_worker_pool =
+        |worker_pools: &mut BTreeMap<_, _>, view: &ViewEntity| match worker_pools
+            .contains_key(&view.view)
+        {
+            true => warn!("Worker pool of the view {} is running already", view.view),
+            false => {
+                let worker_pool_cancellation = cancellation.child_token();
+                let handles = start(view, &worker_pool_cancellation);
+                worker_pools.insert(view.view.clone(), (worker_pool_cancellation, handles));
+            }
+        };
+    for view in &views {
+        start_worker_pool(&mut worker_pools, view);
+    }
+    loop {
+        tokio::select! {
+            Some(command) = commands.recv() => match command {
+                WorkerPoolCommand::Start(view) => start_worker_pool(&mut worker_pools, &view),
+                WorkerPoolCommand::Stop(view, reply) => {
+                    if let Some((worker_pool_cancellation, handles)) = worker_pools.remove(&view) {
+                        worker_pool_cancellation.cancel();
+                        stop(&view, handles).await;
+                        info!("### Worker pool of the view {view} is stopped ###");
+                    }
+                    let _ = reply.send(());
+                }
+            },
+
+            _ = cancellation.cancelled() => break,
+        }
+    }
+    for (view, (_, handles)) in worker_pools {
+        stop(&view, handles).await;
+    }
+}
+
+/// Wait for the (cancelled) workers of the `view` to stop - at most `STOP_TIMEOUT`, then abort them
+async fn stop(view: &str, handles: Vec<JoinHandle<()>>) {
+    let abort_handles: Vec<_> = handles.iter().map(JoinHandle::abort_handle).collect();
+    let stopped = timeout(STOP_TIMEOUT, async {
+        for handle in handles {
+            if let Err(error) = handle.await {
+                error!("###  Worker pool of the view {view} failed to shut down: {error} ###");
+            }
+        }
+    })
+    .await;
+    if stopped.is_err() {
+        warn!("###  Worker pool of the view {view} did not stop within {STOP_TIMEOUT:?}, aborting ###");
+        for abort_handle in abort_handles {
+            abort_handle.abort();
+        }
+    }
+}
+
+/// Spawn the supervised pool of workers streaming the events to the `view`, and the listener waking them on the new events.
+/// `process` handles the next batch of events, returning `true` if the next batch might be waiting already (check `stream_events_to_view`).
+/// A failed (or panicked) worker is restarted with the exponential backoff, until the `cancellation`.
+pub fn spawn_worker_pool<F, Fut>(
+    view: &str,
+    config: &WorkerPoolConfig,
+    process: F,
+    registry: &WorkerRegistry,
+    cancellation: &CancellationToken,
+    db: &Database,
+) -> Vec<JoinHandle<()>>
+where
+    F: Fn() -> Fut + Clone + 'static,
+    Fut: Future<Output = Result<bool, AppError>> + 'static,
+{
+    let wake = Arc::new(Notify::new());
+    let mut handles = vec![actix_web::rt::spawn(listen(
+        view.to_string(),
+        config.polling_interval,
+        wake.clone(),
+        cancellation.clone(),
+        Database { db: db.db.clone() },
+    ))];
+    for worker in 0..config.concurrency.max(1) {
+        handles.push(actix_web::rt::spawn(supervise(
+            view.to_string(),
+            worker,
+            config.clone(),
+            process.clone(),
+            wake.clone(),
+            registry.clone(),
+            cancellation.clone(),
+        )));
+    }
+    info!(
+        "✅ Started {} worker(s) for the view {view}",
+        config.concurrency.max(1)
+    );
+    handles
+}
+
+/// Listen for the new events of the `view`, and wake all its workers - on every notification, or every polling interval at least
+async fn listen(
+    view: String,
+    polling_interval: Duration,
+    wake: Arc<Notify>,
+    cancellation: CancellationToken,
+    db: Database,
+) {
+    loop {
+        match EventListener::connect(&view, polling_interval, &db).await {
+            Ok(mut listener) => loop {
+                tokio::select! {
+                    _ = listener.wait() => wake.notify_waiters(),
+                    _ = cancellation.cancelled() => return,
+                }
+            },
+            Err(error) => {
+                warn!("Event listener of the view {view} failed to connect, polling: {error}");
+                tokio::select! {
+                    _ = sleep(polling_interval) => wake.notify_waiters(),
+                    _ = cancellation.cancelled() => return,
+                }
+            }
+        }
+    }
+}
+
+/// Abort the task when dropped
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run the worker, and restart it with the exponential backoff whenever it fails or panics
+async fn supervise<F, Fut>(
+    view: String,
+    worker: usize,
+    config: WorkerPoolConfig,
+    process: F,
+    wake: Arc<Notify>,
+    registry: WorkerRegistry,
+    cancellation: CancellationToken,
+) where
+    F: Fn() -> Fut + Clone + 'static,
+    Fut: Future<Output = Result<bool, AppError>> + 'static,
+{
+    let mut failures = 0;
+    loop {
+        registry.update(&view, worker, |status| status.state = WorkerState::Running);
+        let started = Instant::now();
+        let running = actix_web::rt::spawn(run(
+            view.clone(),
+            worker,
+            process.clone(),
+            wake.clone(),
+            registry.clone(),
+            cancellation.clone(),
+        ));
+        // The worker is aborted along with its supervisor - the worker pool that does not stop in time is aborted (check `stop`)
+        let _abort = AbortOnDrop(running.abort_handle());
+        let error = match running.await {
+            Ok(Ok(())) => {
+                registry.update(&view, worker, |status| status.state = WorkerState::Stopped);
+                return;
+            }
+            Ok(Err(error)) => error.to_string(),
+            Err(panic) => format!("Worker panicked: {panic}"),
+        };
+
+        // The worker that was running well for a while starts over with the shortest backoff
+        if started.elapsed() > config.max_restart_backoff {
+            failures = 0;
+        }
+        let delay = config
+            .restart_backoff
+            .saturating_mul(2u32.saturating_pow(failures))
+            .min(config.max_restart_backoff);
+        failures += 1;
+        error!(
+            "###  Worker {worker} of the view {view} failed, restarting in {delay:?}: {error} ###"
+        );
+        registry.update(&view, worker, |status| {
+            status.state = WorkerState::Restarting;
+            status.restarts += 1;
+            status.last_error = Some(error);
+        });
+
+        tokio::select! {
+            _ = sleep(delay) => continue,
+            _ = cancellation.cancelled() => {
+                registry.update(&view, worker, |status| status.state = WorkerState::Stopped);
+                return;
+            }
+        }
+    }
+}
+
+/// Process all the pending events, then wait to be woken by the listener - until the cancellation.
+/// The cancellation is checked after every batch, so the worker with a backlog stops without draining it.
+async fn run<F, Fut>(
+    view: String,
+    worker: usize,
+    process: F,
+    wake: Arc<Notify>,
+    registry: WorkerRegistry,
+    cancellation: CancellationToken,
+) -> Result<(), AppError>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<bool, AppError>>,
+{
+    loop {
+        // Register for the wake-up before processing, so the notifications arriving meanwhile are not missed
+        let mut woken = pin!(wake.notified());
+        woken.as_mut().enable();
+
+        while !cancellation.is_cancelled() && process().await? {
+            registry.update(&view, worker, |status| {
+                status.last_processed_at = Some(Utc::now())
+            });
+        }
+
+        tokio::select! {
+            biased;
+            _ = cancellation.cancelled() => {
+                info!("### Gracefully shutting worker {worker} of the view {view} ###");
+                return Ok(());
+            }
+            _ = woken => continue,
+        }
+    }
+}
+
+#[cfg(test)]
+/// Tests for the stream workers
+mod worker_tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use tokio::sync::Notify;
+    use tokio::task::LocalSet;
+    use tokio::time::{sleep, timeout, Instant};
+    use tokio_util::sync::CancellationToken;
+
+    use crate::adapter::database::entity::ViewEntity;
+    use crate::adapter::event_stream::worker::{
+        manage_worker_pools, run, supervise, WorkerPoolConfig, WorkerPoolManager, WorkerRegistry,
+        WorkerState, STOP_TIMEOUT,
+    };
+    use crate::application::error::AppError;
+
+    /// Sets the flag when dropped - the task holding it has exited, or was aborted
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn view(name: &str) -> ViewEntity {
+        ViewEntity {
+            view: name.to_string(),
+            pooling_delay: 500,
+            start_at: Utc::now(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            handler: "projection".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn run_stops_with_backlog_test() {
+        let cancellation = CancellationToken::new();
+        let batches = Arc::new(AtomicUsize::new(0));
+        // The backlog never drains - every batch reports the next one waiting, and the third one cancels the worker
+        let process = {
+            let (cancellation, batches) = (cancellation.clone(), batches.clone());
+            move || {
+                let (cancellation, batches) = (cancellation.clone(), batches.clone());
+                async move {
+                    if batches.fetch_add(1, Ordering::SeqCst) == 2 {
+                        cancellation.cancel();
+                    }
+                    Ok(true)
+                }
+            }
+        };
+
+        let stopped = timeout(
+            Duration::from_secs(1),
+            run(
+                "view".to_string(),
+                0,
+                process,
+                Arc::new(Notify::new()),
+                WorkerRegistry::default(),
+                cancellation,
+            ),
+        )
+        .await;
+        assert_eq!(stopped, Ok(Ok(())));
+        assert_eq!(batches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn supervise_resets_restart_backoff_test() {
+        let config = WorkerPoolConfig {
+            restart_backoff: Duration::from_millis(100),
+            max_restart_backoff: Duration::from_secs(1),
+            ..WorkerPoolConfig::default()
+        };
+        let cancellation = CancellationToken::new();
+        let registry = WorkerRegistry::default();
+        let started = Instant::now();
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        // Three quick failures, one failure after running well for longer than the maximum backoff, and one quick failure after it.
+        // The sixth run stops the worker.
+        let process = {
+            let (cancellation, runs) = (cancellation.clone(), runs.clone());
+            move || {
+                let (cancellation, runs) = (cancellation.clone(), runs.clone());
+                async move {
+                    let run = {
+                        let mut runs = runs.lock().unwrap();
+                        runs.push(started.elapsed());
+                        runs.len()
+                    };
+                    match run {
+                        4 => sleep(Duration::from_secs(2)).await,
+                        6 => {
+                            cancellation.cancel();
+                            return Ok(false);
+                        }
+                        _ => {}
+                    }
+                    Err(AppError::Infrastructure(format!("run {run} failed")))
+                }
+            }
+        };
+
+        LocalSet::new()
+            .run_until(supervise(
+                "view".to_string(),
+                0,
+                config,
+                process,
+                Arc::new(Notify::new()),
+                registry.clone(),
+                cancellation,
+            ))
+            .await;
+
+        let millis = |millis| Duration::from_millis(millis);
+        // The backoff doubles on the consecutive failures, and starts over once the worker was running well
+        assert_eq!(
+            *runs.lock().unwrap(),
+            vec![
+                millis(0),
+                millis(100),
+                millis(300),
+                millis(700),
+                millis(2800),
+                millis(3000)
+            ]
+        );
+        let status = &registry.statuses()[0];
+        assert_eq!(status.state, WorkerState::Stopped);
+        assert_eq!(status.restarts, 5);
+        assert_eq!(status.last_error.as_deref(), Some("run 5 failed"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn supervise_restarts_panicked_worker_test() {
+        let cancellation = CancellationToken::new();
+        let registry = WorkerRegistry::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        // The first run panics, the second one stops the worker
+        let process = {
+            let (cancellation, runs) = (cancellation.clone(), runs.clone());
+            move || {
+                let (cancellation, runs) = (cancellation.clone(), runs.clone());
+                async move {
+                    if runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                        panic!("poison");
+                    }
+                    cancellation.cancel();
+                    Ok(false)
+                }
+            }
+        };
+
+        LocalSet::new()
+            .run_until(supervise(
+                "view".to_string(),
+                0,
+                WorkerPoolConfig::default(),
+                process,
+                Arc::new(Notify::new()),
+                registry.clone(),
+                cancellation,
+            ))
+            .await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        let status = &registry.statuses()[0];
+        assert_eq!(status.state, WorkerState::Stopped);
+        assert_eq!(status.restarts, 1);
+        assert!(status
+            .last_error
+            .as_deref()
+            .is_some_and(|error| error.starts_with("Worker panicked")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stop_replies_after_workers_exit_test() {
+        let cancellation = CancellationToken::new();
+        let exited = Arc::new(AtomicBool::new(false));
+        // The worker finishes its batch in flight once cancelled, and exits
+        let start = {
+            let exited = exited.clone();
+            move |_: &ViewEntity, cancellation: &CancellationToken| {
+                let (exited, cancellation) = (DropFlag(exited.clone()), cancellation.clone());
+                vec![tokio::spawn(async move {
+                    cancellation.cancelled().await;
+                    sleep(Duration::from_secs(1)).await;
+                    drop(exited);
+                })]
+            }
+        };
+        let (manager, commands) = WorkerPoolManager::new(&["projection"]);
+        let managing = tokio::spawn(manage_worker_pools(
+            vec![view("view")],
+            commands,
+            start,
+            cancellation.clone(),
+        ));
+
+        let started = Instant::now();
+        assert_eq!(manager.stop("view").await, Ok(()));
+        assert!(exited.load(Ordering::SeqCst));
+        assert_eq!(started.elapsed(), Duration::from_secs(1));
+
+        // The stopped worker pool can be started again, and stopping the unknown one replies right away
+        manager.start(view("view")).unwrap();
+        assert_eq!(manager.stop("unknown").await, Ok(()));
+
+        cancellation.cancel();
+        managing.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stop_aborts_workers_after_timeout_test() {
+        let cancellation = CancellationToken::new();
+        let aborted = Arc::new(AtomicBool::new(false));
+        // The worker ignores the cancellation - its batch in flight never finishes
+        let start = {
+            let aborted = aborted.clone();
+            move |_: &ViewEntity, _: &CancellationToken| {
+                let aborted = DropFlag(aborted.clone());
+                vec![tokio::spawn(async move {
+                    let _aborted = aborted;
+                    std::future::pending::<()>().await;
+                })]
+            }
+        };
+        let (manager, commands) = WorkerPoolManager::new(&["projection"]);
+        let managing = tokio::spawn(manage_worker_pools(
+            vec![view("view")],
+            commands,
+            start,
+            cancellation.clone(),
+        ));
+
+        let started = Instant::now();
+        assert_eq!(manager.stop("view").await, Ok(()));
+        assert_eq!(started.elapsed(), STOP_TIMEOUT);
+        // Let the aborted worker be dropped
+        tokio::task::yield_now().await;
+        assert!(aborted.load(Ordering::SeqCst));
+
+        cancellation.cancel();
+        managing.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn aborted_supervisor_aborts_worker_test() {
+        let aborted = Arc::new(AtomicBool::new(false));
+        // The batch in flight never finishes
+        let process = {
+            let aborted = aborted.clone();
+            move || {
+                let aborted = DropFlag(aborted.clone());
+                async move {
+                    let _aborted = aborted;
+                    std::future::pending::<Result<bool, AppError>>().await
+                }
+            }
+        };
+
+        LocalSet::new()
+            .run_until(async {
+                let supervisor = tokio::task::spawn_local(supervise(
+                    "view".to_string(),
+                    0,
+                    WorkerPoolConfig::default(),
+                    process,
+                    Arc::new(Notify::new()),
+                    WorkerRegistry::default(),
+                    CancellationToken::new(),
+                ));
+                sleep(Duration::from_secs(1)).await;
+                assert!(!aborted.load(Ordering::SeqCst));
+
+                supervisor.abort();
+                assert!(supervisor.await.unwrap_err().is_cancelled());
+                tokio::task::yield_now().await;
+                assert!(aborted.load(Ordering::SeqCst));
+            })
+            .await;
+    }
+}
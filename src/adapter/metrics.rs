@@ -0,0 +1,220 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::adapter::database::queries::list_view_lags;
+use crate::application::error::AppError;
+use crate::Database;
+
+/// Outcome label of the successfully handled command/reaction
+pub const SUCCESS: &str = "Success";
+/// Outcome label of the command replayed from the idempotency key
+pub const REPLAYED: &str = "Replayed";
+
+/// Application metrics - exposed in the Prometheus text format by the `/metrics` endpoint
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Application metrics - counters and histograms registered in the dedicated Prometheus registry
+pub struct Metrics {
+    registry: Registry,
+    /// Commands handled - per decider, command type and outcome (`Success`, `Replayed` or the error kind)
+    pub commands: IntCounterVec,
+    /// Latency of the decider handling the command (fetch the events, decide, save the new events) - per decider and command type
+    pub decider_latency: HistogramVec,
+    /// Events appended to the event store - per decider and event name
+    pub events_appended: IntCounterVec,
+    /// Latency of leasing the next batch of events of the view from the event store
+    pub stream_polling_latency: HistogramVec,
+    /// Saga reactions - per event name and outcome (`Success` or the error kind)
+    pub saga_reactions: IntCounterVec,
+    /// Events the view failed to process - per view
+    pub nacks: IntCounterVec,
+    /// Events moved to the dead letters - per view
+    pub dead_letters: IntCounterVec,
+    /// Events the view has not processed yet - per view, refreshed on scrape
+    projection_lag_events: IntGaugeVec,
+    /// Decider streams with the events the view has not processed yet - per view, refreshed on scrape
+    projection_lag_streams: IntGaugeVec,
+    /// Age of the oldest event the view has not processed yet - per view, refreshed on scrape
+    projection_lag_seconds: GaugeVec,
+    /// Connections of the DB pool - per state (`idle`, `in_use`), refreshed on scrape
+    db_pool_connections: IntGaugeVec,
+    /// Maximum number of the connections of the DB pool
+    db_pool_max_connections: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("fmodel".to_string()), None)
+            .expect("metrics registry prefix is valid");
+        let metrics = Metrics {
+            commands: IntCounterVec::new(
+                Opts::new("commands_total", "Commands handled"),
+                &["decider", "command", "outcome"],
+            )
+            .expect("metric is valid"),
+            decider_latency: HistogramVec::new(
+                HistogramOpts::new(
+                    "decider_latency_seconds",
+                    "Latency of the decider handling the command",
+                ),
+                &["decider", "command"],
+            )
+            .expect("metric is valid"),
+            events_appended: IntCounterVec::new(
+                Opts::new(
+                    "events_appended_total",
+                    "Events appended to the event store",
+                ),
+                &["decider", "event"],
+            )
+            .expect("metric is valid"),
+            stream_polling_latency: HistogramVec::new(
+                HistogramOpts::new(
+                    "stream_events_polling_latency_seconds",
+                    "Latency of leasing the next batch of events of the view",
+                ),
+                &["view"],
+            )
+            .expect("metric is valid"),
+            saga_reactions: IntCounterVec::new(
+                Opts::new("saga_reactions_total", "Saga reactions to the events"),
+                &["event", "outcome"],
+            )
+            .expect("metric is valid"),
+            nacks: IntCounterVec::new(
+                Opts::new("stream_nacks_total", "Events the view failed to process"),
+                &["view"],
+            )
+            .expect("metric is valid"),
+            dead_letters: IntCounterVec::new(
+                Opts::new(
+                    "stream_dead_letters_total",
+                    "Events moved to the dead letters",
+                ),
+                &["view"],
+            )
+            .expect("metric is valid"),
+            projection_lag_events: IntGaugeVec::new(
+                Opts::new(
+                    "projection_lag_events",
+                    "Events the view has not processed yet",
+                ),
+                &["view", "handler"],
+            )
+            .expect("metric is valid"),
+            projection_lag_streams: IntGaugeVec::new(
+                Opts::new(
+                    "projection_lag_streams",
+                    "Decider streams with the events the view has not processed yet",
+                ),
+                &["view", "handler"],
+            )
+            .expect("metric is valid"),
+            projection_lag_seconds: GaugeVec::new(
+                Opts::new(
+                    "projection_lag_seconds",
+                    "Age of the oldest event the view has not processed yet",
+                ),
+                &["view", "handler"],
+            )
+            .expect("metric is valid"),
+            db_pool_connections: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "Connections of the DB pool"),
+                &["state"],
+            )
+            .expect("metric is valid"),
+            db_pool_max_connections: IntGauge::new(
+                "db_pool_max_connections",
+                "Maximum number of the connections of the DB pool",
+            )
+            .expect("metric is valid"),
+            registry,
+        };
+        let collectors: [Box<dyn Collector>; 12] = [
+            Box::new(metrics.commands.clone()),
+            Box::new(metrics.decider_latency.clone()),
+            Box::new(metrics.events_appended.clone()),
+            Box::new(metrics.stream_polling_latency.clone()),
+            Box::new(metrics.saga_reactions.clone()),
+            Box::new(metrics.nacks.clone()),
+            Box::new(metrics.dead_letters.clone()),
+            Box::new(metrics.projection_lag_events.clone()),
+            Box::new(metrics.projection_lag_streams.clone()),
+            Box::new(metrics.projection_lag_seconds.clone()),
+            Box::new(metrics.db_pool_connections.clone()),
+            Box::new(metrics.db_pool_max_connections.clone()),
+        ];
+        for collector in collectors {
+            metrics
+                .registry
+                .register(collector)
+                .expect("metric is registered once");
+        }
+        metrics
+    }
+
+    /// Count the command handled by the `decider`, and observe the latency of handling it
+    pub fn observe_command<T>(
+        &self,
+        decider: &str,
+        command: &str,
+        elapsed: Duration,
+        result: &Result<T, AppError>,
+    ) {
+        self.decider_latency
+            .with_label_values(&[decider, command])
+            .observe(elapsed.as_secs_f64());
+        self.commands
+            .with_label_values(&[decider, command, outcome(result)])
+            .inc();
+    }
+
+    /// Refresh the gauges sampled from the database, and encode all the metrics in the Prometheus text format
+    pub async fn gather(&self, db: &Database) -> Result<String, AppError> {
+        self.projection_lag_events.reset();
+        self.projection_lag_streams.reset();
+        self.projection_lag_seconds.reset();
+        for lag in list_view_lags(&None, db).await? {
+            let labels = [lag.view.as_str(), lag.handler.as_str()];
+            self.projection_lag_events
+                .with_label_values(&labels)
+                .set(lag.events_behind);
+            self.projection_lag_streams
+                .with_label_values(&labels)
+                .set(lag.streams_behind);
+            self.projection_lag_seconds
+                .with_label_values(&labels)
+                .set(lag.oldest_unprocessed_event_age_secs.unwrap_or_default());
+        }
+
+        let (size, idle) = (db.db.size() as i64, db.db.num_idle() as i64);
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set(size - idle);
+        self.db_pool_max_connections
+            .set(db.db.options().get_max_connections() as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|error| AppError::Infrastructure(error.to_string()))?;
+        String::from_utf8(buffer).map_err(|error| AppError::Infrastructure(error.to_string()))
+    }
+}
+
+/// Outcome label of the result - `Success`, or the kind of the error
+pub fn outcome<T>(result: &Result<T, AppError>) -> &'static str {
+    match result {
+        Ok(_) => SUCCESS,
+        Err(error) => error.kind(),
+    }
+}
@@ -1,5 +1,8 @@
+pub mod configuration;
 pub mod database;
 pub mod event_stream;
+pub mod metrics;
 pub mod publisher;
 pub mod repository;
+pub mod telemetry;
 pub mod web;
@@ -1 +1,2 @@
 pub mod order_action_publisher;
+pub mod restaurant_action_publisher;
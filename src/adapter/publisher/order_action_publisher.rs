@@ -1,20 +1,53 @@
-use crate::adapter::database::error::ErrorMessage;
+use crate::adapter::metrics::METRICS;
 use crate::adapter::repository::event_repository::AggregateEventRepository;
 use crate::application::api::OrderAggregate;
+use crate::application::error::AppError;
+use crate::application::metadata::CommandMetadata;
+use crate::application::retry::RetryPolicy;
 use crate::domain::api::OrderCommand;
+use chrono::Utc;
 use fmodel_rust::saga_manager::ActionPublisher;
 use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+/// The actor issuing the commands published by the OrderActionPublisher
+pub const ACTOR: &str = "OrderSaga";
 
 /// Order action publisher - used by the Saga Manager to publish actions/commands
 pub struct OrderActionPublisher<'a> {
     pub order_aggregate: Arc<OrderAggregate<'a, AggregateEventRepository>>,
+    pub retry_policy: RetryPolicy,
 }
 
 /// Fmodel action publisher implementation fot the OrderActionPublisher
-impl ActionPublisher<OrderCommand, ErrorMessage> for OrderActionPublisher<'_> {
-    async fn publish(&self, commands: &[OrderCommand]) -> Result<Vec<OrderCommand>, ErrorMessage> {
+impl ActionPublisher<OrderCommand, AppError> for OrderActionPublisher<'_> {
+    async fn publish(&self, commands: &[OrderCommand]) -> Result<Vec<OrderCommand>, AppError> {
+        // Metadata of the saga reaction - every command gets its own ID within the same flow
+        let reaction = CommandMetadata::current().unwrap_or_else(|| CommandMetadata::new(ACTOR));
         for command in commands {
-            self.order_aggregate.handle(command).await?;
+            let metadata = CommandMetadata {
+                command_id: Uuid::new_v4(),
+                timestamp: Utc::now(),
+                ..reaction.clone()
+            };
+            let command_type = serde_json::to_value(command)?["type"]
+                .as_str()
+                .unwrap_or("Unknown")
+                .to_string();
+            let started = Instant::now();
+            let result = metadata
+                .scope(
+                    self.retry_policy
+                        .retry_on_conflict("OrderCommand", || self.order_aggregate.handle(command)),
+                )
+                .instrument(
+                    info_span!("aggregate.handle", decider = "Order", command = %command_type),
+                )
+                .await;
+            METRICS.observe_command("Order", &command_type, started.elapsed(), &result);
+            result?;
         }
         Ok(commands.to_vec())
     }
@@ -0,0 +1,59 @@
+use crate::adapter::metrics::METRICS;
+use crate::adapter::repository::event_repository::AggregateEventRepository;
+use crate::application::api::RestaurantAggregate;
+use crate::application::error::AppError;
+use crate::application::metadata::CommandMetadata;
+use crate::application::retry::RetryPolicy;
+use crate::domain::api::RestaurantCommand;
+use chrono::Utc;
+use fmodel_rust::saga_manager::ActionPublisher;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+/// The actor issuing the commands published by the RestaurantActionPublisher
+pub const ACTOR: &str = "RestaurantSaga";
+
+/// Restaurant action publisher - used by the Saga Manager to publish the compensating actions/commands
+pub struct RestaurantActionPublisher<'a> {
+    pub restaurant_aggregate: Arc<RestaurantAggregate<'a, AggregateEventRepository>>,
+    pub retry_policy: RetryPolicy,
+}
+
+/// Fmodel action publisher implementation fot the RestaurantActionPublisher
+impl ActionPublisher<RestaurantCommand, AppError> for RestaurantActionPublisher<'_> {
+    async fn publish(
+        &self,
+        commands: &[RestaurantCommand],
+    ) -> Result<Vec<RestaurantCommand>, AppError> {
+        // Metadata of the saga reaction - every command gets its own ID within the same flow
+        let reaction = CommandMetadata::current().unwrap_or_else(|| CommandMetadata::new(ACTOR));
+        for command in commands {
+            let metadata = CommandMetadata {
+                command_id: Uuid::new_v4(),
+                timestamp: Utc::now(),
+                ..reaction.clone()
+            };
+            let command_type = serde_json::to_value(command)?["type"]
+                .as_str()
+                .unwrap_or("Unknown")
+                .to_string();
+            let started = Instant::now();
+            let result = metadata
+                .scope(
+                    self.retry_policy
+                        .retry_on_conflict("RestaurantCommand", || {
+                            self.restaurant_aggregate.handle(command)
+                        }),
+                )
+                .instrument(
+                    info_span!("aggregate.handle", decider = "Restaurant", command = %command_type),
+                )
+                .await;
+            METRICS.observe_command("Restaurant", &command_type, started.elapsed(), &result);
+            result?;
+        }
+        Ok(commands.to_vec())
+    }
+}
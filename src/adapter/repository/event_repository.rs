@@ -2,11 +2,18 @@ use std::collections::HashMap;
 
 use fmodel_rust::aggregate::EventRepository;
 use fmodel_rust::Identifier;
+use serde_json::Value;
 use uuid::Uuid;
 
 use crate::adapter::database::entity::{EventEntity, NewEventEntity};
-use crate::adapter::database::error::ErrorMessage;
-use crate::adapter::database::queries::{append_events, get_latest_event, list_events};
+use crate::adapter::database::queries::{
+    append_events, get_latest_event, list_correlated_events, list_decider_events, list_events,
+};
+use crate::adapter::metrics::METRICS;
+use crate::adapter::telemetry::trace_context;
+use crate::application::api::{EventEnvelope, EventQueryHandler};
+use crate::application::error::AppError;
+use crate::application::metadata::CommandMetadata;
 use crate::domain::api::{DeciderName, EventName};
 use crate::{adapter, Database};
 /// EventRepository struct
@@ -22,8 +29,8 @@ impl AggregateEventRepository {
     }
 }
 
-/// EventRepository - implementation of Fmodel EventRepository for C, E, Uuid, ErrorMessage, where C and E are constrained with specific traits
-impl<C, E> EventRepository<C, E, Uuid, ErrorMessage> for AggregateEventRepository
+/// EventRepository - implementation of Fmodel EventRepository for C, E, Uuid, AppError, where C and E are constrained with specific traits
+impl<C, E> EventRepository<C, E, Uuid, AppError> for AggregateEventRepository
 where
     C: Identifier + Sync,
     E: Identifier
@@ -34,7 +41,8 @@ where
         + Clone
         + ToEventEntity,
 {
-    async fn fetch_events(&self, command: &C) -> Result<Vec<(E, Uuid)>, ErrorMessage> {
+    #[tracing::instrument(skip_all, fields(decider_id = %command.identifier()))]
+    async fn fetch_events(&self, command: &C) -> Result<Vec<(E, Uuid)>, AppError> {
         tracing::debug!("Fetching events for command: {:?}", command.identifier());
         list_events(&command.identifier(), &self.database)
             .await?
@@ -47,7 +55,11 @@ where
             .collect()
     }
 
-    async fn save(&self, events: &[E]) -> Result<Vec<(E, Uuid)>, ErrorMessage> {
+    #[tracing::instrument(skip_all, fields(events = events.len()))]
+    async fn save(&self, events: &[E]) -> Result<Vec<(E, Uuid)>, AppError> {
+        // Metadata of the command being handled - persisted alongside each event, within the trace of the current span
+        let metadata =
+            CommandMetadata::current().map(|metadata| metadata.with_trace_context(trace_context()));
         let mut result_events = Vec::new();
         let mut new_events = Vec::new();
         // Key is the identifier (decider_id) of the event, value is the latest version of the event for this partition/stream/decider_id
@@ -57,14 +69,14 @@ where
             let latest_version = match latest_versions.get(&event.identifier()) {
                 Some(&v) => Some(v),
                 None => {
-                    let v = <adapter::repository::event_repository::AggregateEventRepository as fmodel_rust::aggregate::EventRepository<C, E, uuid::Uuid, adapter::database::error::ErrorMessage>>::version_provider(self,event).await?;
+                    let v = <adapter::repository::event_repository::AggregateEventRepository as fmodel_rust::aggregate::EventRepository<C, E, uuid::Uuid, AppError>>::version_provider(self,event).await?;
                     if let Some(version) = v {
                         latest_versions.insert(event.identifier().to_owned(), version);
                     }
                     v
                 }
             };
-            let event_request = event.to_event_entity(latest_version)?;
+            let event_request = event.to_event_entity(latest_version, metadata.as_ref())?;
             result_events.push(((*event).to_owned(), event_request.event_id));
             new_events.push(event_request.to_owned());
             // Update the latest version of the event for this partition/stream/decider_id
@@ -72,19 +84,105 @@ where
         }
         tracing::debug!("Saving events...");
         append_events(&new_events, &self.database).await?;
+        for new_event in &new_events {
+            METRICS
+                .events_appended
+                .with_label_values(&[&new_event.decider, &new_event.event])
+                .inc();
+        }
         Ok(result_events)
     }
 
-    async fn version_provider(&self, event: &E) -> Result<Option<Uuid>, ErrorMessage> {
+    async fn version_provider(&self, event: &E) -> Result<Option<Uuid>, AppError> {
         get_latest_event(&event.identifier(), &self.database)
             .await
             .map(|event_entity| event_entity.map(|e| e.event_id))
     }
 }
 
+/// Implementation of EventQueryHandler for AggregateEventRepository
+impl EventQueryHandler for AggregateEventRepository {
+    /// Get the ordered page of event envelopes of the `decider` stream by `decider_id` - `NotFound` if the stream has no events
+    async fn get_events(
+        &self,
+        decider: &str,
+        decider_id: &str,
+        from_offset: i64,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope>, AppError> {
+        let (decider, decider_id) = (decider.to_string(), decider_id.to_string());
+        let events =
+            list_decider_events(&decider, &decider_id, &from_offset, &limit, &self.database)
+                .await?;
+        // The empty page past the end of the stream is fine, but the stream itself must exist
+        if events.is_empty()
+            && list_decider_events(&decider, &decider_id, &0, &1, &self.database)
+                .await?
+                .is_empty()
+        {
+            return Err(AppError::NotFound(format!(
+                "{decider} {decider_id} not found"
+            )));
+        }
+        Ok(events
+            .into_iter()
+            .map(|entity| entity.to_event_envelope())
+            .collect())
+    }
+    /// Get the ordered event envelopes of a single flow by `correlation_id`
+    async fn get_correlated_events(
+        &self,
+        correlation_id: &Uuid,
+    ) -> Result<Vec<EventEnvelope>, AppError> {
+        Ok(
+            list_correlated_events(&correlation_id.to_string(), &self.database)
+                .await?
+                .into_iter()
+                .map(|entity| entity.to_event_envelope())
+                .collect(),
+        )
+    }
+}
+
+/// Map the EventEntity into the event envelope
+trait ToEventEnvelope {
+    fn to_event_envelope(self) -> EventEnvelope;
+}
+
+/// Map the EventEntity into the event envelope
+impl ToEventEnvelope for EventEntity {
+    fn to_event_envelope(self) -> EventEnvelope {
+        EventEnvelope {
+            decider: self.decider,
+            decider_id: self.decider_id,
+            event: self.event,
+            event_id: self.event_id,
+            previous_id: self.previous_id,
+            offset: self.offset,
+            created_at: self.created_at,
+            command_id: self.command_id,
+            r#final: self.r#final,
+            metadata: self.metadata,
+            payload: self.data,
+        }
+    }
+}
+
+/// Map the EventEntity into the metadata of the command that caused the event
+pub trait ToCommandMetadata {
+    fn to_command_metadata(&self) -> Option<CommandMetadata>;
+}
+
+/// Map the EventEntity into the metadata of the command that caused the event - `None` for the events stored without metadata
+impl ToCommandMetadata for EventEntity {
+    fn to_command_metadata(&self) -> Option<CommandMetadata> {
+        serde_json::from_value(self.metadata.clone()).ok()
+    }
+}
+
 /// Map the EventEntity into the domain events
 pub trait ToEvent<E> {
-    fn to_event(&self) -> Result<E, ErrorMessage>;
+    fn to_event(&self) -> Result<E, AppError>;
 }
 
 /// Map the EventEntity into the domain events
@@ -92,26 +190,35 @@ impl<E> ToEvent<E> for EventEntity
 where
     E: serde::de::DeserializeOwned,
 {
-    fn to_event(&self) -> Result<E, ErrorMessage> {
+    fn to_event(&self) -> Result<E, AppError> {
         let value = self.data.clone();
-        serde_json::from_value(value).map_err(|err| ErrorMessage {
-            message: err.to_string(),
-        })
+        Ok(serde_json::from_value(value)?)
     }
 }
 
 trait ToEventEntity {
-    fn to_event_entity(&self, version: Option<Uuid>) -> Result<NewEventEntity, ErrorMessage>;
+    fn to_event_entity(
+        &self,
+        version: Option<Uuid>,
+        metadata: Option<&CommandMetadata>,
+    ) -> Result<NewEventEntity, AppError>;
 }
 /// Map from domain events of type OrderEvent to EventEntity
 impl<E> ToEventEntity for E
 where
     E: Identifier + EventName + DeciderName + serde::ser::Serialize,
 {
-    fn to_event_entity(&self, version: Option<Uuid>) -> Result<NewEventEntity, ErrorMessage> {
-        let data = serde_json::to_value(self).map_err(|err| ErrorMessage {
-            message: err.to_string(),
-        })?;
+    fn to_event_entity(
+        &self,
+        version: Option<Uuid>,
+        metadata: Option<&CommandMetadata>,
+    ) -> Result<NewEventEntity, AppError> {
+        let data = serde_json::to_value(self)?;
+        let command_id = metadata.map(|metadata| metadata.command_id);
+        let metadata = match metadata {
+            Some(metadata) => serde_json::to_value(metadata)?,
+            None => Value::Object(Default::default()),
+        };
 
         Ok(NewEventEntity {
             event: self.event_name(),
@@ -119,9 +226,10 @@ where
             decider: self.decider_name(),
             decider_id: self.identifier(),
             data,
-            command_id: None,
+            command_id,
             previous_id: version,
             r#final: false,
+            metadata,
         })
     }
 }
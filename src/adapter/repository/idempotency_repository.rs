@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::adapter::database::queries::{
+    complete_idempotency_key, get_idempotency_key, purge_idempotency_keys, release_idempotency_key,
+    reserve_idempotency_key,
+};
+use crate::application::api::IdempotencyHandler;
+use crate::application::error::AppError;
+use crate::Database;
+
+/// How often the idempotency keys older than the retention window are purged
+const PURGE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// IdempotencyRepository struct
+pub struct IdempotencyRepository {
+    database: Database,
+    /// How long the idempotency keys are remembered
+    retention: Duration,
+    /// How long the idempotency key is reserved for the command being handled, before the repeated command can reclaim it
+    lease: Duration,
+}
+
+/// IdempotencyRepository - struct implementation
+impl IdempotencyRepository {
+    /// Create a new IdempotencyRepository
+    pub fn new(database: Database, retention: Duration, lease: Duration) -> Self {
+        IdempotencyRepository {
+            database,
+            retention,
+            lease,
+        }
+    }
+}
+
+/// Purge the idempotency keys older than the `retention` window periodically - until the cancellation.
+/// The expired keys are reclaimed on reserve anyway, so this only keeps the table small.
+pub async fn purge_idempotency_keys_periodically(
+    database: Database,
+    retention: Duration,
+    cancellation: CancellationToken,
+) {
+    loop {
+        match purge_idempotency_keys(&retention.as_secs_f64(), &database).await {
+            Ok(purged) if purged > 0 => info!("Purged {purged} expired idempotency keys"),
+            Ok(_) => {}
+            Err(error) => warn!("Purging the expired idempotency keys failed: {error}"),
+        }
+        tokio::select! {
+            _ = sleep(PURGE_INTERVAL) => continue,
+            _ = cancellation.cancelled() => return,
+        }
+    }
+}
+
+/// Implementation of IdempotencyHandler for IdempotencyRepository
+impl IdempotencyHandler for IdempotencyRepository {
+    /// Reserve the idempotency `key` for the `decider` `command` - leased, so that the abandoned reservation is reclaimed by the repeated command
+    async fn reserve(
+        &self,
+        decider: &str,
+        key: &str,
+        command: &Value,
+    ) -> Result<Option<Value>, AppError> {
+        let (decider, key) = (decider.to_string(), key.to_string());
+        if reserve_idempotency_key(
+            &decider,
+            &key,
+            command,
+            &self.lease.as_secs_f64(),
+            &self.retention.as_secs_f64(),
+            &self.database,
+        )
+        .await?
+        .is_some()
+        {
+            return Ok(None);
+        }
+        let Some(entity) = get_idempotency_key(&decider, &key, &self.database).await? else {
+            // Released concurrently - the client should retry
+            return Err(AppError::Conflict(format!(
+                "{decider} command with idempotency key '{key}' was just released, please retry"
+            )));
+        };
+        if entity.command != *command {
+            return Err(AppError::Validation(format!(
+                "Idempotency key '{key}' was already used by a different {decider} command"
+            )));
+        }
+        match entity.response {
+            Some(events) => Ok(Some(events)),
+            // The reservation is not reclaimed while its lease lasts
+            None => Err(AppError::Conflict(format!(
+                "{decider} command with idempotency key '{key}' is still being handled, please retry"
+            ))),
+        }
+    }
+    /// Remember the `events` produced by the command handled under the idempotency `key`
+    async fn complete(&self, decider: &str, key: &str, events: &Value) -> Result<(), AppError> {
+        complete_idempotency_key(
+            &decider.to_string(),
+            &key.to_string(),
+            events,
+            &self.database,
+        )
+        .await?;
+        Ok(())
+    }
+    /// Release the idempotency `key` of the failed command
+    async fn release(&self, decider: &str, key: &str) -> Result<(), AppError> {
+        release_idempotency_key(&decider.to_string(), &key.to_string(), &self.database).await?;
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
 pub mod event_repository;
+pub mod idempotency_repository;
 pub mod order_view_state_repository;
 pub mod restaurant_view_state_repository;
@@ -2,37 +2,61 @@ use fmodel_rust::materialized_view::ViewStateRepository;
 use fmodel_rust::Identifier;
 
 use crate::adapter::database::entity::OrderEntity;
-use crate::adapter::database::error::ErrorMessage;
-use crate::adapter::database::queries::{get_all_orders, get_order, upsert_order};
+use crate::adapter::database::queries::{
+    get_all_orders, get_order, get_order_in_view, upsert_order,
+};
 use crate::application::api::OrderQueryHandler;
+use crate::application::error::AppError;
 use crate::domain::api::OrderEvent;
 use crate::domain::order_view::OrderViewState;
 use crate::Database;
 
 /// OrderViewStateRepository struct
+/// Queries the active projection view (version), and projects the events into the rows of its own projection `view`
 pub struct OrderViewStateRepository {
     database: Database,
+    view: Option<String>,
 }
 
 /// OrderViewStateRepository - struct implementation
 impl OrderViewStateRepository {
-    /// Create a new OrderViewStateRepository
+    /// Create a new OrderViewStateRepository - querying the active projection view
     pub fn new(database: Database) -> Self {
-        OrderViewStateRepository { database }
+        OrderViewStateRepository {
+            database,
+            view: None,
+        }
+    }
+
+    /// Create a new OrderViewStateRepository - projecting the events into the rows of the projection `view`
+    pub fn for_view(database: Database, view: &str) -> Self {
+        OrderViewStateRepository {
+            database,
+            view: Some(view.to_string()),
+        }
+    }
+
+    /// The projection view the events are projected into
+    fn view(&self) -> Result<&String, AppError> {
+        self.view.as_ref().ok_or_else(|| {
+            AppError::Infrastructure(
+                "OrderViewStateRepository is not bound to a projection view".to_string(),
+            )
+        })
     }
 }
 
 /// Implementation of OrderQueryHandler for OrderViewStateRepository
 impl OrderQueryHandler for OrderViewStateRepository {
     /// Get the Order view state by `id`
-    async fn get_order(&self, id: &str) -> Result<Option<OrderViewState>, ErrorMessage> {
+    async fn get_order(&self, id: &str) -> Result<Option<OrderViewState>, AppError> {
         get_order(&id.to_string(), &self.database)
             .await?
             .map(|entity| entity.to_order())
             .transpose()
     }
     /// Get all the Order view states
-    async fn get_all_orders(&self) -> Result<Vec<OrderViewState>, ErrorMessage> {
+    async fn get_all_orders(&self) -> Result<Vec<OrderViewState>, AppError> {
         get_all_orders(&self.database)
             .await?
             .into_iter()
@@ -41,15 +65,15 @@ impl OrderQueryHandler for OrderViewStateRepository {
     }
 }
 
-/// OrderViewStateRepository - implementation of Fmodel ViewStateRepository for OrderEvent, OrderViewState, ErrorMessage
-impl ViewStateRepository<OrderEvent, Option<OrderViewState>, ErrorMessage>
+/// OrderViewStateRepository - implementation of Fmodel ViewStateRepository for OrderEvent, OrderViewState, AppError
+impl ViewStateRepository<OrderEvent, Option<OrderViewState>, AppError>
     for OrderViewStateRepository
 {
     async fn fetch_state(
         &self,
         event: &OrderEvent,
-    ) -> Result<Option<Option<OrderViewState>>, ErrorMessage> {
-        get_order(&event.identifier(), &self.database)
+    ) -> Result<Option<Option<OrderViewState>>, AppError> {
+        get_order_in_view(&event.identifier(), self.view()?, &self.database)
             .await?
             .map(|entity| entity.to_order())
             .transpose()
@@ -59,10 +83,10 @@ impl ViewStateRepository<OrderEvent, Option<OrderViewState>, ErrorMessage>
     async fn save(
         &self,
         state: &Option<OrderViewState>,
-    ) -> Result<Option<OrderViewState>, ErrorMessage> {
+    ) -> Result<Option<OrderViewState>, AppError> {
         match state {
             Some(state) => {
-                let order_entity = state.to_order_entity()?;
+                let order_entity = state.to_order_entity(self.view()?)?;
                 let stored_state = upsert_order(&order_entity, &self.database).await?;
                 Ok(Some(stored_state.to_order()?))
             }
@@ -73,35 +97,32 @@ impl ViewStateRepository<OrderEvent, Option<OrderViewState>, ErrorMessage>
 
 /// Map to OrderViewState
 trait ToOrder {
-    fn to_order(&self) -> Result<OrderViewState, ErrorMessage>;
+    fn to_order(&self) -> Result<OrderViewState, AppError>;
 }
 
 /// Map the OrderEntity to OrderViewState
 impl ToOrder for OrderEntity {
     /// Map the OrderEntity to OrderViewState
-    fn to_order(&self) -> Result<OrderViewState, ErrorMessage> {
+    fn to_order(&self) -> Result<OrderViewState, AppError> {
         let value = self.data.clone();
-        serde_json::from_value(value).map_err(|err| ErrorMessage {
-            message: err.to_string(),
-        })
+        Ok(serde_json::from_value(value)?)
     }
 }
 
 /// Map to OrderEntity
 trait ToOrderEntity {
-    fn to_order_entity(&self) -> Result<OrderEntity, ErrorMessage>;
+    fn to_order_entity(&self, view: &str) -> Result<OrderEntity, AppError>;
 }
 /// Map theOrderViewState to OrderEntity
 impl ToOrderEntity for OrderViewState {
     /// Map theOrderViewState to OrderEntity
-    fn to_order_entity(&self) -> Result<OrderEntity, ErrorMessage> {
+    fn to_order_entity(&self, view: &str) -> Result<OrderEntity, AppError> {
         serde_json::to_value(self)
-            .map_err(|err| ErrorMessage {
-                message: err.to_string(),
-            })
+            .map_err(AppError::from)
             .map(|value| OrderEntity {
                 id: self.identifier.to_string(),
                 data: value,
+                view: view.to_string(),
             })
     }
 }
@@ -2,37 +2,61 @@ use fmodel_rust::materialized_view::ViewStateRepository;
 use fmodel_rust::Identifier;
 
 use crate::adapter::database::entity::RestaurantEntity;
-use crate::adapter::database::error::ErrorMessage;
-use crate::adapter::database::queries::{get_all_restaurants, get_restaurant, upsert_restaurant};
+use crate::adapter::database::queries::{
+    get_all_restaurants, get_restaurant, get_restaurant_in_view, upsert_restaurant,
+};
 use crate::application::api::RestaurantQueryHandler;
+use crate::application::error::AppError;
 use crate::domain::api::RestaurantEvent;
 use crate::domain::restaurant_view::RestaurantViewState;
 use crate::Database;
 
 /// RestaurantViewStateRepository struct
+/// Queries the active projection view (version), and projects the events into the rows of its own projection `view`
 pub struct RestaurantViewStateRepository {
     database: Database,
+    view: Option<String>,
 }
 
 /// RestaurantViewStateRepository - struct implementation
 impl RestaurantViewStateRepository {
-    /// Create a new RestaurantViewStateRepository
+    /// Create a new RestaurantViewStateRepository - querying the active projection view
     pub fn new(database: Database) -> Self {
-        RestaurantViewStateRepository { database }
+        RestaurantViewStateRepository {
+            database,
+            view: None,
+        }
+    }
+
+    /// Create a new RestaurantViewStateRepository - projecting the events into the rows of the projection `view`
+    pub fn for_view(database: Database, view: &str) -> Self {
+        RestaurantViewStateRepository {
+            database,
+            view: Some(view.to_string()),
+        }
+    }
+
+    /// The projection view the events are projected into
+    fn view(&self) -> Result<&String, AppError> {
+        self.view.as_ref().ok_or_else(|| {
+            AppError::Infrastructure(
+                "RestaurantViewStateRepository is not bound to a projection view".to_string(),
+            )
+        })
     }
 }
 
 /// Implementation of RestaurantQueryHandler for RestaurantViewStateRepository
 impl RestaurantQueryHandler for RestaurantViewStateRepository {
     /// Get the Restaurant view state by `id`
-    async fn get_restaurant(&self, id: &str) -> Result<Option<RestaurantViewState>, ErrorMessage> {
+    async fn get_restaurant(&self, id: &str) -> Result<Option<RestaurantViewState>, AppError> {
         get_restaurant(&id.to_string(), &self.database)
             .await?
             .map(|entity| entity.to_restaurant())
             .transpose()
     }
     /// Get all the Restaurant view states
-    async fn get_all_restaurants(&self) -> Result<Vec<RestaurantViewState>, ErrorMessage> {
+    async fn get_all_restaurants(&self) -> Result<Vec<RestaurantViewState>, AppError> {
         get_all_restaurants(&self.database)
             .await?
             .into_iter()
@@ -41,15 +65,15 @@ impl RestaurantQueryHandler for RestaurantViewStateRepository {
     }
 }
 
-/// RestaurantViewStateRepository - implementation of Fmodel ViewStateRepository for RestaurantEvent, RestaurantViewState, ErrorMessage
-impl ViewStateRepository<RestaurantEvent, Option<RestaurantViewState>, ErrorMessage>
+/// RestaurantViewStateRepository - implementation of Fmodel ViewStateRepository for RestaurantEvent, RestaurantViewState, AppError
+impl ViewStateRepository<RestaurantEvent, Option<RestaurantViewState>, AppError>
     for RestaurantViewStateRepository
 {
     async fn fetch_state(
         &self,
         event: &RestaurantEvent,
-    ) -> Result<Option<Option<RestaurantViewState>>, ErrorMessage> {
-        get_restaurant(&event.identifier(), &self.database)
+    ) -> Result<Option<Option<RestaurantViewState>>, AppError> {
+        get_restaurant_in_view(&event.identifier(), self.view()?, &self.database)
             .await?
             .map(|entity| entity.to_restaurant())
             .transpose()
@@ -59,10 +83,10 @@ impl ViewStateRepository<RestaurantEvent, Option<RestaurantViewState>, ErrorMess
     async fn save(
         &self,
         state: &Option<RestaurantViewState>,
-    ) -> Result<Option<RestaurantViewState>, ErrorMessage> {
+    ) -> Result<Option<RestaurantViewState>, AppError> {
         match state {
             Some(state) => {
-                let restaurant_entity = state.to_restaurant_entity()?;
+                let restaurant_entity = state.to_restaurant_entity(self.view()?)?;
                 let stored_state = upsert_restaurant(&restaurant_entity, &self.database).await?;
                 Ok(Some(stored_state.to_restaurant()?))
             }
@@ -73,35 +97,32 @@ impl ViewStateRepository<RestaurantEvent, Option<RestaurantViewState>, ErrorMess
 
 /// Map to RestaurantViewState
 trait ToRestaurant {
-    fn to_restaurant(&self) -> Result<RestaurantViewState, ErrorMessage>;
+    fn to_restaurant(&self) -> Result<RestaurantViewState, AppError>;
 }
 
 /// Map the RestaurantEntity to RestaurantViewState
 impl ToRestaurant for RestaurantEntity {
     /// Map the RestaurantEntity to RestaurantViewState
-    fn to_restaurant(&self) -> Result<RestaurantViewState, ErrorMessage> {
+    fn to_restaurant(&self) -> Result<RestaurantViewState, AppError> {
         let value = self.data.clone();
-        serde_json::from_value(value).map_err(|err| ErrorMessage {
-            message: err.to_string(),
-        })
+        Ok(serde_json::from_value(value)?)
     }
 }
 
 /// Map to RestaurantEntity
 trait ToRestaurantEntity {
-    fn to_restaurant_entity(&self) -> Result<RestaurantEntity, ErrorMessage>;
+    fn to_restaurant_entity(&self, view: &str) -> Result<RestaurantEntity, AppError>;
 }
 /// Map RestaurantViewState to RestaurantEntity
 impl ToRestaurantEntity for RestaurantViewState {
     /// Map the RestaurantViewState to RestaurantEntity
-    fn to_restaurant_entity(&self) -> Result<RestaurantEntity, ErrorMessage> {
+    fn to_restaurant_entity(&self, view: &str) -> Result<RestaurantEntity, AppError> {
         serde_json::to_value(self)
-            .map_err(|err| ErrorMessage {
-                message: err.to_string(),
-            })
+            .map_err(AppError::from)
             .map(|value| RestaurantEntity {
                 id: self.identifier.to_string(),
                 data: value,
+                view: view.to_string(),
             })
     }
 }
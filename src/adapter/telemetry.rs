@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::env::var;
+
+use actix_web::dev::ServiceRequest;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::{info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::application::error::AppError;
+
+/// OTLP (HTTP) collector endpoint - environment variable. The traces are exported only if it is set, e.g. `http://localhost:4318`
+pub const OTEL_EXPORTER_OTLP_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+/// W3C trace context headers
+const TRACEPARENT_HEADER: &str = "traceparent";
+const TRACESTATE_HEADER: &str = "tracestate";
+/// Name of the service the traces are reported by
+const SERVICE_NAME: &str = "fmodel-rust-demo";
+
+/// Create the tracer provider exporting the spans to the OTLP collector - `None` if the collector endpoint is not configured
+pub fn tracer_provider() -> Result<Option<SdkTracerProvider>, AppError> {
+    if var(OTEL_EXPORTER_OTLP_ENDPOINT).is_err() {
+        return Ok(None);
+    }
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .build()
+        .map_err(|error| AppError::Infrastructure(error.to_string()))?;
+    Ok(Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+            .build(),
+    ))
+}
+
+/// The tracer of the service
+pub fn tracer(provider: &SdkTracerProvider) -> SdkTracer {
+    provider.tracer(SERVICE_NAME)
+}
+
+/// W3C trace context (`traceparent`, `tracestate`) of the current span - empty if the tracing is not exported
+pub fn trace_context() -> HashMap<String, String> {
+    let mut trace_context = HashMap::new();
+    TraceContextPropagator::new().inject_context(&Span::current().context(), &mut trace_context);
+    trace_context
+}
+
+/// Span of the HTTP request - continues the trace of the W3C `traceparent` request header, if any
+pub fn request_span(request: &ServiceRequest) -> Span {
+    let span = info_span!("http.request", method = %request.method(), path = %request.path());
+    let trace_context: HashMap<String, String> = [TRACEPARENT_HEADER, TRACESTATE_HEADER]
+        .into_iter()
+        .filter_map(|name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect();
+    follow_trace_context(&span, &trace_context);
+    span
+}
+
+/// Continue the trace of the W3C `trace_context` within the `span` - the span becomes the child of the span that produced the trace context
+pub fn follow_trace_context(span: &Span, trace_context: &HashMap<String, String>) {
+    if trace_context.is_empty() {
+        return;
+    }
+    let parent = TraceContextPropagator::new().extract(trace_context);
+    if let Err(error) = span.set_parent(parent) {
+        tracing::debug!("Trace context of the span can not be set: {error}");
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::adapter::database::entity::{StreamLagEntity, ViewEntity, ViewLagEntity};
+use crate::adapter::database::queries::{
+    activate_projection, discard_dead_letter, get_active_projection, get_dead_letter,
+    get_last_offset, get_view, get_view_progress, list_dead_letters, list_lagging_streams,
+    list_leased_streams, list_view_lags, list_views, register_view, reset_projection_view,
+    retry_dead_letter,
+};
+use crate::adapter::event_stream::dead_letter::check_retry_in_order;
+use crate::adapter::event_stream::view_stream;
+use crate::adapter::event_stream::worker::WorkerPoolManager;
+use crate::application::error::AppError;
+use crate::Database;
+
+/// Default frequency (in milliseconds) of polling the database for the new events, if the notification is missed
+const DEFAULT_POOLING_DELAY: i64 = 500;
+
+/// View registration request
+#[derive(Deserialize)]
+struct RegisterViewRequest {
+    /// Unique name of the view
+    view: String,
+    /// Name of the event handler the events are streamed to - `projection` or `saga`
+    handler: String,
+    /// Frequency (in milliseconds) of polling the database for the new events, if the notification is missed
+    pooling_delay: Option<i64>,
+    /// The point in time the event streaming starts from - `now` by default
+    start_at: Option<DateTime<Utc>>,
+}
+
+#[post("/admin/views")]
+async fn register_view_handler(
+    request: web::Json<RegisterViewRequest>,
+    worker_pool_manager: web::Data<WorkerPoolManager>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.into_inner();
+    if request.view.trim().is_empty() {
+        return Err(AppError::Validation("view must not be blank".to_string()));
+    }
+    let pooling_delay = request.pooling_delay.unwrap_or(DEFAULT_POOLING_DELAY);
+    if pooling_delay <= 0 {
+        return Err(AppError::Validation(
+            "pooling_delay must be positive".to_string(),
+        ));
+    }
+    worker_pool_manager.validate(&request.handler)?;
+    let view = register_view(
+        &request.view,
+        &request.handler,
+        &pooling_delay,
+        &request.start_at,
+        &database,
+    )
+    .await?;
+    let result = json!(view);
+    worker_pool_manager.start(view)?;
+
+    Ok(HttpResponse::Created().json(result))
+}
+
+#[get("/admin/views")]
+async fn get_views_handler(database: web::Data<Database>) -> Result<HttpResponse, AppError> {
+    let result = list_views(&database).await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+#[get("/admin/views/{view}/progress")]
+async fn get_view_progress_handler(
+    view: web::Path<String>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let view = get_view(&view, &database)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("View {view} not found")))?;
+    let result = get_view_progress(&view.view, &database).await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+/// Rebuild the projection view - replay the full event log into its emptied rows of the projection tables.
+/// The workers of the view are stopped first - they finish the batch in flight, and are aborted if it takes longer than 10 seconds.
+/// Check the progress of the replay with `/admin/views/{view}/progress`.
+#[post("/admin/views/{view}/rebuild")]
+async fn rebuild_view_handler(
+    view: web::Path<String>,
+    worker_pool_manager: web::Data<WorkerPoolManager>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let view = get_projection_view(&view, "rebuilt", &database).await?;
+    if get_active_projection(&database).await?.view == view.view {
+        return Err(AppError::Conflict(format!(
+            "View {} can not be rebuilt - it is serving the queries, activate another projection view first",
+            view.view
+        )));
+    }
+
+    let name = view.view.clone();
+    worker_pool_manager.stop(&name).await?;
+    let reset = reset_projection_view(&name, &database).await;
+    worker_pool_manager.start(view)?;
+    tracing::info!(
+        "Projection view {name} is reset, {} decider stream(s) are being replayed",
+        reset?
+    );
+    let result = get_view_progress(&name, &database).await?;
+
+    Ok(HttpResponse::Accepted().json(json!(result)))
+}
+
+#[get("/admin/projection")]
+async fn get_active_projection_handler(
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let result = get_active_projection(&database).await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+/// Activate the projection view - switch the queries to it atomically, once it has caught up with the head of the event log
+#[post("/admin/views/{view}/activate")]
+async fn activate_view_handler(
+    view: web::Path<String>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let view = get_projection_view(&view, "activated", &database).await?;
+    let progress = get_view_progress(&view.view, &database).await?;
+    if progress.processed_events < progress.total_events {
+        return Err(AppError::Conflict(format!(
+            "View {} can not be activated - it has not caught up yet, {} of {} events processed",
+            view.view, progress.processed_events, progress.total_events
+        )));
+    }
+    let result = activate_projection(&view.view, &database).await?;
+    tracing::info!("Projection view {} is serving the queries", view.view);
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+/// Get the view handled by the projection - the other handlers (saga) can not be rebuilt nor activated
+async fn get_projection_view(
+    view: &String,
+    action: &str,
+    database: &Database,
+) -> Result<ViewEntity, AppError> {
+    let view = get_view(view, database)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("View {view} not found")))?;
+    if view.handler != view_stream::HANDLER {
+        return Err(AppError::Validation(format!(
+            "View {} can not be {action} - only the '{}' views can be",
+            view.view,
+            view_stream::HANDLER,
+        )));
+    }
+    Ok(view)
+}
+
+/// Default maximum number of the lagging decider streams listed per request
+const DEFAULT_LAGGING_STREAMS_LIMIT: i64 = 100;
+
+/// Streams query parameters
+#[derive(Deserialize)]
+struct StreamsQuery {
+    /// The view to report the streams of - all the views if missing
+    view: Option<String>,
+    /// List the decider streams behind the head of the stream as well - the longest waiting first
+    #[serde(default)]
+    lagging: bool,
+    /// Maximum number of the lagging decider streams listed
+    limit: Option<i64>,
+}
+
+/// Stream health of the view - how far behind it is, and which decider streams are leased (or lagging)
+#[derive(Serialize)]
+struct ViewStreams {
+    #[serde(flatten)]
+    lag: ViewLagEntity,
+    leased: Vec<StreamLagEntity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lagging: Option<Vec<StreamLagEntity>>,
+}
+
+/// Report the lag of the `view` and `saga` consumers - per view
+#[get("/admin/streams")]
+async fn get_streams_handler(
+    query: web::Query<StreamsQuery>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_LAGGING_STREAMS_LIMIT);
+    if limit <= 0 {
+        return Err(AppError::Validation(
+            "Limit of the lagging streams must be positive".to_string(),
+        ));
+    }
+    let lags = list_view_lags(&query.view, &database).await?;
+    if let (Some(view), true) = (&query.view, lags.is_empty()) {
+        return Err(AppError::NotFound(format!("View {view} not found")));
+    }
+    let mut leased = group_by_view(list_leased_streams(&query.view, &database).await?);
+    let mut lagging = match query.lagging {
+        true => Some(group_by_view(
+            list_lagging_streams(&query.view, &limit, &database).await?,
+        )),
+        false => None,
+    };
+    let result: Vec<ViewStreams> = lags
+        .into_iter()
+        .map(|lag| ViewStreams {
+            leased: leased.remove(&lag.view).unwrap_or_default(),
+            lagging: lagging
+                .as_mut()
+                .map(|lagging| lagging.remove(&lag.view).unwrap_or_default()),
+            lag,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+/// Group the decider streams by the view
+fn group_by_view(streams: Vec<StreamLagEntity>) -> HashMap<String, Vec<StreamLagEntity>> {
+    let mut grouped: HashMap<String, Vec<StreamLagEntity>> = HashMap::new();
+    for stream in streams {
+        grouped.entry(stream.view.clone()).or_default().push(stream);
+    }
+    grouped
+}
+
+/// Dead letters query parameters
+#[derive(Deserialize)]
+struct DeadLettersQuery {
+    /// The view to list the dead letters of - all the views if missing
+    view: Option<String>,
+}
+
+#[get("/admin/dead-letters")]
+async fn get_dead_letters_handler(
+    query: web::Query<DeadLettersQuery>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let result = list_dead_letters(&query.into_inner().view, &database).await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+/// Retry the dead letter - the view processes the event once again.
+/// The event is retried only as long as the view has not processed any later event of its decider stream - otherwise it would be applied out of the stream order,
+/// over the newer state of the view. Such dead letter is rejected with `409 Conflict`: rebuild the projection view (`/admin/views/{view}/rebuild`) to apply it in order, or discard it.
+#[post("/admin/dead-letters/{view}/{event_id}/retry")]
+async fn retry_dead_letter_handler(
+    path: web::Path<(String, String)>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let (view, event_id) = path.into_inner();
+    let event_id = Uuid::parse_str(&event_id)?;
+    let dead_letter = get_dead_letter(&view, &event_id, &database)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Dead letter {event_id} of the view {view} not found"
+            ))
+        })?;
+    let last_offset = get_last_offset(&view, &dead_letter.decider_id, &database).await?;
+    check_retry_in_order(&dead_letter, last_offset)?;
+    let result = retry_dead_letter(&view, &event_id, &database)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Dead letter {event_id} of the view {view} not found"
+            ))
+        })?;
+
+    Ok(HttpResponse::Accepted().json(json!(result)))
+}
+
+#[delete("/admin/dead-letters/{view}/{event_id}")]
+async fn discard_dead_letter_handler(
+    path: web::Path<(String, String)>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let (view, event_id) = path.into_inner();
+    let event_id = Uuid::parse_str(&event_id)?;
+    discard_dead_letter(&view, &event_id, &database)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Dead letter {event_id} of the view {view} not found"
+            ))
+        })?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(conf: &mut web::ServiceConfig) {
+    conf.service(register_view_handler)
+        .service(get_views_handler)
+        .service(get_view_progress_handler)
+        .service(rebuild_view_handler)
+        .service(activate_view_handler)
+        .service(get_active_projection_handler)
+        .service(get_streams_handler)
+        .service(get_dead_letters_handler)
+        .service(retry_dead_letter_handler)
+        .service(discard_dead_letter_handler);
+}
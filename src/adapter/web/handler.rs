@@ -1,125 +1,405 @@
+use crate::adapter::event_stream::worker::WorkerRegistry;
+use crate::adapter::metrics::{METRICS, REPLAYED};
 use crate::adapter::repository::event_repository::AggregateEventRepository;
+use crate::adapter::repository::idempotency_repository::IdempotencyRepository;
 use crate::adapter::repository::order_view_state_repository::OrderViewStateRepository;
 use crate::adapter::repository::restaurant_view_state_repository::RestaurantViewStateRepository;
-use crate::application::api::{Application, OrderQueryHandler, RestaurantQueryHandler};
+use crate::adapter::web::metadata::{
+    IdempotencyKey, COMMAND_ID_HEADER, CORRELATION_ID_HEADER, IDEMPOTENT_REPLAYED_HEADER,
+};
+use crate::adapter::web::problem::{json_error_handler, query_error_handler};
+use crate::adapter::web::{admin, health};
+use crate::application::api::{
+    Application, EventQueryHandler, IdempotencyHandler, OrderQueryHandler, RestaurantQueryHandler,
+};
+use crate::application::error::AppError;
+use crate::application::metadata::CommandMetadata;
 use crate::domain::api::{OrderCommand, RestaurantCommand};
+use crate::Database;
 use actix_web::{get, post, web, HttpResponse, Responder};
-use serde_json::json;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::time::Instant;
+use tracing::{info_span, warn, Instrument};
+use uuid::Uuid;
 
-#[get("/healthchecker")]
-async fn health_checker_handler(
-    _application: web::Data<
-        Application<
-            '_,
-            AggregateEventRepository,
-            AggregateEventRepository,
-            OrderViewStateRepository,
-            RestaurantViewStateRepository,
-        >,
+/// Application state shared by all the handlers
+type ApplicationState<'a> = web::Data<
+    Application<
+        'a,
+        AggregateEventRepository,
+        AggregateEventRepository,
+        OrderViewStateRepository,
+        RestaurantViewStateRepository,
+        AggregateEventRepository,
+        IdempotencyRepository,
     >,
-) -> impl Responder {
+>;
+
+/// Maximum number of events returned by the event history query
+const MAX_EVENTS_LIMIT: i64 = 1000;
+
+/// Event history query parameters
+#[derive(Deserialize)]
+struct EventHistoryQuery {
+    /// The offset (inclusive) to start the page from
+    from_offset: Option<i64>,
+    /// The maximum number of events in the page
+    limit: Option<i64>,
+}
+
+/// Correlated events query parameters
+#[derive(Deserialize)]
+struct CorrelationQuery {
+    /// The correlation ID of the flow
+    correlation_id: Uuid,
+}
+
+/// Handle the command once per idempotency key - the events produced by the command are remembered under the key and replayed on the repeated request.
+/// Returns the events, and whether they are replayed.
+/// The repeated request is answered with `409 Conflict` and `Retry-After` while the command is still being handled - until the reservation lease expires.
+async fn handle_idempotently<F, Fut>(
+    idempotency_handler: &impl IdempotencyHandler,
+    decider: &str,
+    key: IdempotencyKey,
+    command: Value,
+    handle: F,
+) -> Result<(Value, bool), AppError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Value, AppError>>,
+{
+    let command_type = command["type"].as_str().unwrap_or("Unknown").to_string();
+    let handle = || async {
+        let started = Instant::now();
+        let result = handle()
+            .instrument(info_span!("aggregate.handle", decider, command = %command_type))
+            .await;
+        METRICS.observe_command(decider, &command_type, started.elapsed(), &result);
+        result
+    };
+    let Some(key) = key.0 else {
+        return Ok((handle().await?, false));
+    };
+    if let Some(events) = idempotency_handler.reserve(decider, &key, &command).await? {
+        METRICS
+            .commands
+            .with_label_values(&[decider, &command_type, REPLAYED])
+            .inc();
+        return Ok((events, true));
+    }
+    match handle().await {
+        Ok(events) => {
+            // The events are already appended - failing to remember them must not fail the handled command.
+            // The repeated command reclaims the key once its reservation lease expires (see `IdempotencyHandler::reserve`)
+            if let Err(error) = idempotency_handler.complete(decider, &key, &events).await {
+                warn!("Events of the {decider} command with idempotency key '{key}' were not remembered: {error}");
+            }
+            Ok((events, false))
+        }
+        Err(error) => {
+            idempotency_handler.release(decider, &key).await?;
+            Err(error)
+        }
+    }
+}
+
+/// Metrics of the application in the Prometheus text format
+#[get("/metrics")]
+async fn metrics_handler(database: web::Data<Database>) -> Result<HttpResponse, AppError> {
+    let result = METRICS.gather(&database).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(result))
+}
+
+#[get("/healthchecker")]
+async fn health_checker_handler(workers: web::Data<WorkerRegistry>) -> impl Responder {
     const MESSAGE: &str = "Fmodel demo is running!";
+    const DEGRADED_MESSAGE: &str =
+        "Fmodel demo is running, but some of the event stream workers are not!";
 
-    HttpResponse::Ok().json(json!({"status": "success","message": MESSAGE}))
+    if workers.is_healthy() {
+        HttpResponse::Ok()
+            .json(json!({"status": "success","message": MESSAGE, "workers": workers.statuses()}))
+    } else {
+        HttpResponse::ServiceUnavailable().json(
+            json!({"status": "failure","message": DEGRADED_MESSAGE, "workers": workers.statuses()}),
+        )
+    }
 }
 
 #[post("/commands/order")]
 async fn order_command_handler(
     command: web::Json<OrderCommand>,
-    application: web::Data<
-        Application<
-            '_,
-            AggregateEventRepository,
-            AggregateEventRepository,
-            OrderViewStateRepository,
-            RestaurantViewStateRepository,
-        >,
-    >,
-) -> impl Responder {
-    let result = application
-        .order_aggregate
-        .handle(&command.into_inner())
-        .await;
+    metadata: CommandMetadata,
+    idempotency_key: IdempotencyKey,
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let command = command.into_inner();
+    let (command_id, correlation_id) = (metadata.command_id, metadata.correlation_id);
+    let (result, replayed) = handle_idempotently(
+        &application.idempotency_handler,
+        "Order",
+        idempotency_key,
+        json!(command),
+        || async {
+            // Stamped by the server once the idempotency key is checked - the repeated command is the same command
+            let command = command.to_owned().stamped(Utc::now());
+            let events = metadata
+                .scope(
+                    application
+                        .retry_policy
+                        .retry_on_conflict("OrderCommand", || {
+                            application.order_aggregate.handle(&command)
+                        }),
+                )
+                .await?;
+            Ok(json!(events))
+        },
+    )
+    .await?;
 
-    match result {
-        Ok(result) => HttpResponse::Ok().json(json!(result)),
-        Err(err) => HttpResponse::InternalServerError().json(json!(err)),
-    }
+    Ok(HttpResponse::Ok()
+        .insert_header((COMMAND_ID_HEADER, command_id.to_string()))
+        .insert_header((CORRELATION_ID_HEADER, correlation_id.to_string()))
+        .insert_header((IDEMPOTENT_REPLAYED_HEADER, replayed.to_string()))
+        .json(result))
 }
 
 #[get("/queries/order")]
 async fn get_all_orders_handler(
-    application: web::Data<
-        Application<
-            '_,
-            AggregateEventRepository,
-            AggregateEventRepository,
-            OrderViewStateRepository,
-            RestaurantViewStateRepository,
-        >,
-    >,
-) -> impl Responder {
-    let result = application.order_query_handler.get_all_orders().await;
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let result = application.order_query_handler.get_all_orders().await?;
 
-    match result {
-        Ok(result) => HttpResponse::Ok().json(json!(result)),
-        Err(err) => HttpResponse::InternalServerError().json(json!(err)),
-    }
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+#[get("/queries/order/{id}")]
+async fn get_order_handler(
+    id: web::Path<String>,
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let id = Uuid::parse_str(&id)?;
+    let result = application
+        .order_query_handler
+        .get_order(&id.to_string())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Order {id} not found")))?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
 }
 
 #[post("/commands/restaurant")]
 async fn restaurant_command_handler(
     command: web::Json<RestaurantCommand>,
-    application: web::Data<
-        Application<
-            '_,
-            AggregateEventRepository,
-            AggregateEventRepository,
-            OrderViewStateRepository,
-            RestaurantViewStateRepository,
-        >,
-    >,
-) -> impl Responder {
-    let result = application
-        .restaurant_aggregate
-        .handle(&command.into_inner())
-        .await;
+    metadata: CommandMetadata,
+    idempotency_key: IdempotencyKey,
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let command = command.into_inner();
+    let (command_id, correlation_id) = (metadata.command_id, metadata.correlation_id);
+    let (result, replayed) = handle_idempotently(
+        &application.idempotency_handler,
+        "Restaurant",
+        idempotency_key,
+        json!(command),
+        || async {
+            // Stamped by the server once the idempotency key is checked - the repeated command is the same command
+            let command = command.to_owned().stamped(Utc::now());
+            let events = metadata
+                .scope(
+                    application
+                        .retry_policy
+                        .retry_on_conflict("RestaurantCommand", || {
+                            application.restaurant_aggregate.handle(&command)
+                        }),
+                )
+                .await?;
+            Ok(json!(events))
+        },
+    )
+    .await?;
 
-    match result {
-        Ok(result) => HttpResponse::Ok().json(json!(result)),
-        Err(err) => HttpResponse::InternalServerError().json(json!(err)),
-    }
+    Ok(HttpResponse::Ok()
+        .insert_header((COMMAND_ID_HEADER, command_id.to_string()))
+        .insert_header((CORRELATION_ID_HEADER, correlation_id.to_string()))
+        .insert_header((IDEMPOTENT_REPLAYED_HEADER, replayed.to_string()))
+        .json(result))
 }
 
 #[get("/queries/restaurant")]
 async fn get_all_restaurants_handler(
-    application: web::Data<
-        Application<
-            '_,
-            AggregateEventRepository,
-            AggregateEventRepository,
-            OrderViewStateRepository,
-            RestaurantViewStateRepository,
-        >,
-    >,
-) -> impl Responder {
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
     let result = application
         .restaurant_query_handler
         .get_all_restaurants()
-        .await;
+        .await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+#[get("/queries/restaurant/{id}")]
+async fn get_restaurant_handler(
+    id: web::Path<String>,
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let id = Uuid::parse_str(&id)?;
+    let result = application
+        .restaurant_query_handler
+        .get_restaurant(&id.to_string())
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Restaurant {id} not found")))?;
 
-    match result {
-        Ok(result) => HttpResponse::Ok().json(json!(result)),
-        Err(err) => HttpResponse::InternalServerError().json(json!(err)),
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+#[get("/events/{decider}/{decider_id}")]
+async fn get_events_handler(
+    path: web::Path<(String, String)>,
+    query: web::Query<EventHistoryQuery>,
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let (decider, decider_id) = path.into_inner();
+    let from_offset = query.from_offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(100);
+    if !(1..=MAX_EVENTS_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {MAX_EVENTS_LIMIT}"
+        )));
     }
+    let result = application
+        .event_query_handler
+        .get_events(&decider, &decider_id, from_offset, limit)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
+}
+
+#[get("/events")]
+async fn get_correlated_events_handler(
+    query: web::Query<CorrelationQuery>,
+    application: ApplicationState<'_>,
+) -> Result<HttpResponse, AppError> {
+    let result = application
+        .event_query_handler
+        .get_correlated_events(&query.correlation_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(json!(result)))
 }
 
 pub fn config(conf: &mut web::ServiceConfig) {
     let scope = web::scope("/api")
+        .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+        .app_data(web::QueryConfig::default().error_handler(query_error_handler))
         .service(health_checker_handler)
         .service(restaurant_command_handler)
         .service(order_command_handler)
         .service(get_all_restaurants_handler)
-        .service(get_all_orders_handler);
-    conf.service(scope);
+        .service(get_restaurant_handler)
+        .service(get_all_orders_handler)
+        .service(get_order_handler)
+        .service(get_events_handler)
+        .service(get_correlated_events_handler)
+        .configure(admin::config)
+        .configure(health::config);
+    conf.service(scope).service(metrics_handler);
+}
+
+#[cfg(test)]
+/// Tests for the idempotent command handling
+mod handler_tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use chrono::Utc;
+    use serde_json::{json, Value};
+
+    use crate::adapter::web::handler::handle_idempotently;
+    use crate::adapter::web::metadata::IdempotencyKey;
+    use crate::application::api::IdempotencyHandler;
+    use crate::application::error::AppError;
+    use crate::domain::api::OrderCommand;
+
+    /// Idempotency keys in memory - the commands and their events by key
+    #[derive(Default)]
+    struct InMemoryIdempotencyHandler {
+        keys: Mutex<HashMap<String, (Value, Option<Value>)>>,
+    }
+
+    impl IdempotencyHandler for InMemoryIdempotencyHandler {
+        async fn reserve(
+            &self,
+            decider: &str,
+            key: &str,
+            command: &Value,
+        ) -> Result<Option<Value>, AppError> {
+            let mut keys = self.keys.lock().unwrap();
+            match keys.get(key) {
+                None => {
+                    keys.insert(key.to_string(), (command.clone(), None));
+                    Ok(None)
+                }
+                Some((reserved, _)) if reserved != command => Err(AppError::Validation(format!(
+                    "Idempotency key '{key}' was already used by a different {decider} command"
+                ))),
+                Some((_, events)) => events.clone().map(Some).ok_or_else(|| {
+                    AppError::Conflict(format!("Idempotency key '{key}' is still being handled"))
+                }),
+            }
+        }
+        async fn complete(
+            &self,
+            _decider: &str,
+            key: &str,
+            events: &Value,
+        ) -> Result<(), AppError> {
+            if let Some((_, response)) = self.keys.lock().unwrap().get_mut(key) {
+                *response = Some(events.clone());
+            }
+            Ok(())
+        }
+        async fn release(&self, _decider: &str, key: &str) -> Result<(), AppError> {
+            self.keys.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_command_stamped_by_server_test() {
+        let idempotency_handler = InMemoryIdempotencyHandler::default();
+        // The time the command happens `at` is stamped when handled - the time supplied by the client is ignored
+        let request = json!({
+            "type": "Accept",
+            "identifier": "02f09a3f-1624-3b1d-8409-44eff7708207",
+            "at": "2000-01-01T00:00:00Z"
+        });
+        let handle = |request: &Value| {
+            let command: OrderCommand = serde_json::from_value(request.clone()).unwrap();
+            handle_idempotently(
+                &idempotency_handler,
+                "Order",
+                IdempotencyKey(Some("accept-order".to_string())),
+                json!(command),
+                // The stamped command stands for the events it produces
+                move || async move { Ok(json!(command.stamped(Utc::now()))) },
+            )
+        };
+
+        let (events, replayed) = handle(&request).await.unwrap();
+        assert!(!replayed);
+        assert!(events["at"].is_string());
+        assert_ne!(events["at"], request["at"]);
+
+        // The repeated request replays the originally produced events, instead of being a different command
+        let (replayed_events, replayed) = handle(&request).await.unwrap();
+        assert!(replayed);
+        assert_eq!(replayed_events, events);
+    }
 }
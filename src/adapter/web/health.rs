@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use actix_web::{get, web, HttpResponse};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::adapter::configuration::HealthConfig;
+use crate::adapter::database::queries::{
+    get_active_projection, list_applied_migrations, list_view_lags, ping,
+};
+use crate::adapter::database::registry::missing_deciders;
+use crate::adapter::database::MIGRATOR;
+use crate::adapter::event_stream::view_stream;
+use crate::adapter::event_stream::worker::{WorkerRegistry, WorkerState};
+use crate::application::error::AppError;
+use crate::domain::api::{Event, EventCatalogue};
+use crate::Database;
+
+/// Status of the health check
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+enum Status {
+    Up,
+    Down,
+}
+
+/// Result of the single health check - its status, and the details explaining it
+#[derive(Serialize, Debug)]
+struct Check {
+    status: Status,
+    details: Value,
+}
+
+impl Check {
+    fn up(details: Value) -> Self {
+        Check {
+            status: Status::Up,
+            details,
+        }
+    }
+
+    fn down(details: Value) -> Self {
+        Check {
+            status: Status::Down,
+            details,
+        }
+    }
+
+    /// The check failed to run - it is down
+    fn failed(error: AppError) -> Self {
+        Check::down(json!({"error": error.to_string()}))
+    }
+}
+
+/// Health report - up only if all the checks are up
+#[derive(Serialize, Debug)]
+struct Report {
+    status: Status,
+    checks: BTreeMap<&'static str, Check>,
+}
+
+impl Report {
+    fn new(checks: BTreeMap<&'static str, Check>) -> Self {
+        let status = match checks.values().all(|check| check.status == Status::Up) {
+            true => Status::Up,
+            false => Status::Down,
+        };
+        Report { status, checks }
+    }
+
+    fn into_response(self) -> HttpResponse {
+        match self.status {
+            Status::Up => HttpResponse::Ok().json(self),
+            Status::Down => HttpResponse::ServiceUnavailable().json(self),
+        }
+    }
+}
+
+/// Liveness - the process is up and serving the requests. It does not depend on the database, so the instance is not restarted on the database outage
+#[get("/health/live")]
+async fn liveness_handler() -> HttpResponse {
+    Report::new(BTreeMap::new()).into_response()
+}
+
+/// Readiness - the instance can serve the traffic: the database is reachable, the migrations are applied, the deciders are registered, and the event stream workers are running and not lagging
+#[get("/health/ready")]
+async fn readiness_handler(
+    workers: web::Data<WorkerRegistry>,
+    config: web::Data<HealthConfig>,
+    database: web::Data<Database>,
+) -> HttpResponse {
+    let mut checks = BTreeMap::new();
+    checks.insert("database", check_database(&database).await);
+    checks.insert("migrations", check_migrations(&database).await);
+    checks.insert("deciders", check_deciders(&database).await);
+    checks.insert("workers", check_workers(&workers));
+    checks.insert("lag", check_lag(&config, &database).await);
+
+    Report::new(checks).into_response()
+}
+
+/// The database is reachable
+async fn check_database(database: &Database) -> Check {
+    let started = Instant::now();
+    match ping(database).await {
+        Ok(_) => Check::up(json!({"latency_ms": started.elapsed().as_millis()})),
+        Err(error) => Check::failed(error),
+    }
+}
+
+/// All the migrations embedded in the application are applied
+async fn check_migrations(database: &Database) -> Check {
+    let applied = match list_applied_migrations(database).await {
+        Ok(applied) => applied,
+        Err(error) => return Check::failed(error),
+    };
+    let missing: Vec<String> = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .filter(|migration| !applied.contains(&migration.version))
+        .map(|migration| format!("{}_{}", migration.version, migration.description))
+        .collect();
+    match missing.is_empty() {
+        true => Check::up(json!({"applied": applied.len()})),
+        false => Check::down(json!({"missing": missing})),
+    }
+}
+
+/// The catalogue of the deciders and their events is registered in the event store
+async fn check_deciders(database: &Database) -> Check {
+    let catalogue = Event::event_catalogue();
+    match missing_deciders(&catalogue, database).await {
+        Ok(missing) if missing.is_empty() => Check::up(json!({"registered": catalogue.len()})),
+        Ok(missing) => Check::down(json!({
+            "missing": missing
+                .iter()
+                .map(|(decider, event)| format!("{decider}/{event}"))
+                .collect::<Vec<_>>()
+        })),
+        Err(error) => Check::failed(error),
+    }
+}
+
+/// All the event stream workers are running
+fn check_workers(workers: &WorkerRegistry) -> Check {
+    let statuses = workers.statuses();
+    let (running, not_running): (Vec<_>, Vec<_>) = statuses
+        .into_iter()
+        .partition(|status| status.state == WorkerState::Running);
+    match not_running.is_empty() {
+        true => Check::up(json!({"running": running.len()})),
+        false => Check::down(json!({"running": running.len(), "not_running": not_running})),
+    }
+}
+
+/// No view lags behind the event log for longer than `max_lag_secs` - the inactive projection views (catching up, or retired) are not checked
+async fn check_lag(config: &HealthConfig, database: &Database) -> Check {
+    let (lags, active_projection) = match (
+        list_view_lags(&None, database).await,
+        get_active_projection(database).await,
+    ) {
+        (Ok(lags), Ok(active_projection)) => (lags, active_projection),
+        (Err(error), _) | (_, Err(error)) => return Check::failed(error),
+    };
+    let lagging: Vec<Value> = lags
+        .into_iter()
+        .filter(|lag| lag.handler != view_stream::HANDLER || lag.view == active_projection.view)
+        .filter(|lag| {
+            lag.oldest_unprocessed_event_age_secs
+                .is_some_and(|age| age > config.max_lag_secs as f64)
+        })
+        .map(|lag| {
+            json!({
+                "view": lag.view,
+                "events_behind": lag.events_behind,
+                "oldest_unprocessed_event_age_secs": lag.oldest_unprocessed_event_age_secs,
+            })
+        })
+        .collect();
+    match lagging.is_empty() {
+        true => Check::up(json!({"max_lag_secs": config.max_lag_secs})),
+        false => Check::down(json!({"max_lag_secs": config.max_lag_secs, "lagging": lagging})),
+    }
+}
+
+pub fn config(conf: &mut web::ServiceConfig) {
+    conf.service(liveness_handler).service(readiness_handler);
+}
@@ -0,0 +1,90 @@
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use uuid::Uuid;
+
+use crate::application::error::AppError;
+use crate::application::metadata::CommandMetadata;
+
+/// Header carrying the command ID - generated if missing
+pub const COMMAND_ID_HEADER: &str = "X-Command-Id";
+/// Header carrying the correlation ID - the command ID is used if missing
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+/// Header carrying the causation ID - optional
+pub const CAUSATION_ID_HEADER: &str = "X-Causation-Id";
+/// Header carrying the actor issuing the command - `anonymous` if missing
+pub const ACTOR_HEADER: &str = "X-Actor";
+/// Header carrying the idempotency key of the command - the command ID is used if missing
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// Header marking the response that replays the events of the already handled command
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Idempotency key of the command - `None` if neither the idempotency key nor the command ID is supplied by the client
+pub struct IdempotencyKey(pub Option<String>);
+
+/// Extract the command metadata from the request headers
+impl FromRequest for CommandMetadata {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(command_metadata(req))
+    }
+}
+
+/// Extract the idempotency key from the request headers
+impl FromRequest for IdempotencyKey {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(idempotency_key(req))
+    }
+}
+
+fn idempotency_key(req: &HttpRequest) -> Result<IdempotencyKey, AppError> {
+    match header(req, IDEMPOTENCY_KEY_HEADER)? {
+        Some(key) if key.trim().is_empty() => Err(AppError::BadRequest(format!(
+            "{IDEMPOTENCY_KEY_HEADER}: must not be blank"
+        ))),
+        Some(key) => Ok(IdempotencyKey(Some(key))),
+        None => Ok(IdempotencyKey(
+            uuid_header(req, COMMAND_ID_HEADER)?.map(|command_id| command_id.to_string()),
+        )),
+    }
+}
+
+fn command_metadata(req: &HttpRequest) -> Result<CommandMetadata, AppError> {
+    let actor = header(req, ACTOR_HEADER)?.unwrap_or_else(|| "anonymous".to_string());
+    let mut metadata = CommandMetadata::new(&actor);
+    if let Some(command_id) = uuid_header(req, COMMAND_ID_HEADER)? {
+        metadata.command_id = command_id;
+        metadata.correlation_id = command_id;
+    }
+    if let Some(correlation_id) = uuid_header(req, CORRELATION_ID_HEADER)? {
+        metadata.correlation_id = correlation_id;
+    }
+    metadata.causation_id = uuid_header(req, CAUSATION_ID_HEADER)?;
+    Ok(metadata)
+}
+
+fn header(req: &HttpRequest, name: &str) -> Result<Option<String>, AppError> {
+    req.headers()
+        .get(name)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|err| AppError::BadRequest(format!("{name}: {err}")))
+        })
+        .transpose()
+}
+
+fn uuid_header(req: &HttpRequest, name: &str) -> Result<Option<Uuid>, AppError> {
+    header(req, name)?
+        .map(|value| {
+            Uuid::parse_str(&value).map_err(|err| AppError::BadRequest(format!("{name}: {err}")))
+        })
+        .transpose()
+}
@@ -1 +1,5 @@
+pub mod admin;
 pub mod handler;
+pub mod health;
+pub mod metadata;
+pub mod problem;
@@ -0,0 +1,78 @@
+use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::application::error::AppError;
+
+/// Media type of the RFC 7807 problem details
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Seconds the client should wait before retrying the conflicting request - sent in the `Retry-After` header of every `409 Conflict`.
+/// The conflicts are transient: a concurrent change of the stream, or a command with the same idempotency key that is still being handled.
+pub const CONFLICT_RETRY_AFTER_SECS: u64 = 1;
+
+/// RFC 7807 Problem Details - the body of every error response
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProblemDetails {
+    /// A URI reference that identifies the problem type
+    pub r#type: String,
+    /// A short, human-readable summary of the problem type
+    pub title: String,
+    /// The HTTP status code
+    pub status: u16,
+    /// A human-readable explanation specific to this occurrence of the problem
+    pub detail: String,
+}
+
+/// Map the AppError to the HTTP status code and the RFC 7807 problem+json body
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::StreamClosed(_) => StatusCode::GONE,
+            AppError::Infrastructure(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let problem_type = match self {
+            AppError::Conflict(_) => "conflict",
+            AppError::NotFound(_) => "not-found",
+            AppError::BadRequest(_) => "bad-request",
+            AppError::Validation(_) => "validation",
+            AppError::StreamClosed(_) => "stream-closed",
+            AppError::Infrastructure(_) => "infrastructure",
+        };
+        let mut response = HttpResponse::build(status);
+        if let AppError::Conflict(_) = self {
+            response.insert_header((RETRY_AFTER, CONFLICT_RETRY_AFTER_SECS.to_string()));
+        }
+        response.content_type(PROBLEM_JSON).json(ProblemDetails {
+            r#type: format!("/problems/{problem_type}"),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: self.message().to_string(),
+        })
+    }
+}
+
+/// JSON body extractor error handler - syntax errors are `BadRequest`, well-formed JSON that does not match the command is `Validation`
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match &err {
+        JsonPayloadError::Deserialize(error) if error.is_data() => {
+            AppError::Validation(err.to_string()).into()
+        }
+        _ => AppError::BadRequest(err.to_string()).into(),
+    }
+}
+
+/// Query string extractor error handler - malformed query parameters are `BadRequest`
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::BadRequest(err.to_string()).into()
+}
@@ -1,13 +1,17 @@
+use chrono::{DateTime, Utc};
 use fmodel_rust::aggregate::{EventRepository, EventSourcedAggregate};
 use fmodel_rust::decider::Decider;
 use fmodel_rust::materialized_view::MaterializedView;
 use fmodel_rust::saga::Saga;
 use fmodel_rust::saga_manager::SagaManager;
 use fmodel_rust::view::View;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::adapter::database::error::ErrorMessage;
+use crate::application::error::AppError;
+use crate::application::retry::RetryPolicy;
 use crate::domain::api::{OrderCommand, OrderEvent, RestaurantCommand, RestaurantEvent};
 use crate::domain::order_decider::Order;
 use crate::domain::order_view::OrderViewState;
@@ -20,9 +24,9 @@ pub type OrderAggregate<'a, R> = EventSourcedAggregate<
     Option<Order>,
     OrderEvent,
     R,
-    Decider<'a, OrderCommand, Option<Order>, OrderEvent, ErrorMessage>,
+    Decider<'a, OrderCommand, Option<Order>, OrderEvent, AppError>,
     Uuid,
-    ErrorMessage,
+    AppError,
 >;
 
 /// Convenient RestaurantAggregate type alias - Command side of CQRS pattern
@@ -31,36 +35,100 @@ pub type RestaurantAggregate<'a, R> = EventSourcedAggregate<
     Option<Restaurant>,
     RestaurantEvent,
     R,
-    Decider<'a, RestaurantCommand, Option<Restaurant>, RestaurantEvent, ErrorMessage>,
+    Decider<'a, RestaurantCommand, Option<Restaurant>, RestaurantEvent, AppError>,
     Uuid,
-    ErrorMessage,
+    AppError,
 >;
 
 /// RestaurantQueryHandler trait - Query side of CQRS pattern
 pub trait RestaurantQueryHandler {
     /// Get the Restaurant view state by `id`
-    #[allow(dead_code)]
-    async fn get_restaurant(&self, id: &str) -> Result<Option<RestaurantViewState>, ErrorMessage>;
+    async fn get_restaurant(&self, id: &str) -> Result<Option<RestaurantViewState>, AppError>;
     /// Get all the Restaurant view states
-    async fn get_all_restaurants(&self) -> Result<Vec<RestaurantViewState>, ErrorMessage>;
+    async fn get_all_restaurants(&self) -> Result<Vec<RestaurantViewState>, AppError>;
 }
 
 /// OrderQueryHandler trait - Query side of CQRS pattern
 pub trait OrderQueryHandler {
     /// Get the Order view state by `id`
-    #[allow(dead_code)]
-    async fn get_order(&self, id: &str) -> Result<Option<OrderViewState>, ErrorMessage>;
+    async fn get_order(&self, id: &str) -> Result<Option<OrderViewState>, AppError>;
     /// Get all the Order view states
-    async fn get_all_orders(&self) -> Result<Vec<OrderViewState>, ErrorMessage>;
+    async fn get_all_orders(&self) -> Result<Vec<OrderViewState>, AppError>;
+}
+
+/// Event envelope - the stored event together with its event store metadata
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventEnvelope {
+    /// Decider name/type
+    pub decider: String,
+    /// Business identifier of the decider
+    pub decider_id: String,
+    /// Event name/type
+    pub event: String,
+    /// Event ID
+    pub event_id: Uuid,
+    /// Previous event ID in the decider stream - `None` for the first event
+    pub previous_id: Option<Uuid>,
+    /// Global ordering sequence/offset of the event
+    pub offset: i64,
+    /// The timestamp of the event insertion
+    pub created_at: Option<DateTime<Utc>>,
+    /// Command ID causing this event
+    pub command_id: Option<Uuid>,
+    /// Is the decider stream final/closed with this event
+    pub r#final: bool,
+    /// Metadata of the command causing this event - correlation ID, causation ID, actor and timestamp
+    pub metadata: Value,
+    /// Event data
+    pub payload: Value,
+}
+
+/// EventQueryHandler trait - Query side of the event store
+pub trait EventQueryHandler {
+    /// Get the ordered page of event envelopes of the `decider` stream by `decider_id`, starting from the `from_offset` (inclusive)
+    /// Fails with `AppError::NotFound` if the stream has no events at all - the page past the end of the existing stream is empty
+    async fn get_events(
+        &self,
+        decider: &str,
+        decider_id: &str,
+        from_offset: i64,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope>, AppError>;
+    /// Get the ordered event envelopes of a single flow by `correlation_id`, across all the deciders
+    async fn get_correlated_events(
+        &self,
+        correlation_id: &Uuid,
+    ) -> Result<Vec<EventEnvelope>, AppError>;
+}
+
+/// IdempotencyHandler trait - remembers the events produced by the command under the client supplied idempotency key
+pub trait IdempotencyHandler {
+    /// Reserve the idempotency `key` for the `decider` `command`.
+    /// Returns the originally produced events if the key was already used by the same command, or `None` if the command should be handled now.
+    /// Fails with `AppError::Conflict` if the command with the same key is still being handled, and with `AppError::Validation` if the key was used by a different command.
+    /// The reservation is leased: if the handling is abandoned (the request is dropped, the process crashes) or its events are not remembered (`complete` failed),
+    /// the repeated command reclaims the key once the lease expires, and is handled again.
+    async fn reserve(
+        &self,
+        decider: &str,
+        key: &str,
+        command: &Value,
+    ) -> Result<Option<Value>, AppError>;
+    /// Remember the `events` produced by the command handled under the idempotency `key`
+    async fn complete(&self, decider: &str, key: &str, events: &Value) -> Result<(), AppError>;
+    /// Release the idempotency `key` of the failed command, so that it can be retried with the same key
+    async fn release(&self, decider: &str, key: &str) -> Result<(), AppError>;
 }
 
 /// Application struct - A product of the application layer - A cluster of command handling (aggregate) and query handling components
 pub struct Application<
     'a,
-    OR: EventRepository<OrderCommand, OrderEvent, Uuid, ErrorMessage>,
-    RR: EventRepository<RestaurantCommand, RestaurantEvent, Uuid, ErrorMessage>,
+    OR: EventRepository<OrderCommand, OrderEvent, Uuid, AppError>,
+    RR: EventRepository<RestaurantCommand, RestaurantEvent, Uuid, AppError>,
     OQH: OrderQueryHandler,
     RQH: RestaurantQueryHandler,
+    EQH: EventQueryHandler,
+    IH: IdempotencyHandler,
 > {
     /// Restaurant aggregate - Command side of CQRS pattern - Command handler for Restaurant
     pub restaurant_aggregate: Arc<RestaurantAggregate<'a, RR>>,
@@ -70,6 +138,12 @@ pub struct Application<
     pub restaurant_query_handler: RQH,
     /// Order query handler - Query side of CQRS pattern - Query handler for Order
     pub order_query_handler: OQH,
+    /// Event query handler - Query side of the event store - Event history of the deciders
+    pub event_query_handler: EQH,
+    /// Idempotency handler - Command side of CQRS pattern - Replays the events of the repeated commands
+    pub idempotency_handler: IH,
+    /// Retry policy for the command handling on optimistic locking conflicts
+    pub retry_policy: RetryPolicy,
 }
 
 /// Convenient OrderMaterializedView type alias - Query side of CQRS pattern
@@ -78,7 +152,7 @@ pub type RestaurantMaterializedView<'a, R> = MaterializedView<
     RestaurantEvent,
     R,
     View<'a, Option<RestaurantViewState>, RestaurantEvent>,
-    ErrorMessage,
+    AppError,
 >;
 /// Convenient OrderMaterializedView type alias - Query side of CQRS pattern
 pub type OrderMaterializedView<'a, R> = MaterializedView<
@@ -86,7 +160,7 @@ pub type OrderMaterializedView<'a, R> = MaterializedView<
     OrderEvent,
     R,
     View<'a, Option<OrderViewState>, OrderEvent>,
-    ErrorMessage,
+    AppError,
 >;
 
 /// Convenient OrderSagaManager type alias - Saga pattern
@@ -95,5 +169,14 @@ pub type OrderSagaManager<'a, P> = SagaManager<
     RestaurantEvent,
     P,
     Saga<'a, RestaurantEvent, OrderCommand>,
-    ErrorMessage,
+    AppError,
+>;
+
+/// Convenient RestaurantSagaManager type alias - Saga pattern
+pub type RestaurantSagaManager<'a, P> = SagaManager<
+    RestaurantCommand,
+    OrderEvent,
+    P,
+    Saga<'a, OrderEvent, RestaurantCommand>,
+    AppError,
 >;
@@ -0,0 +1,77 @@
+use std::fmt;
+
+/// Application error - a typed error that is carried through the aggregates, repositories and views, and returned to the client
+#[derive(Clone, PartialEq)]
+pub enum AppError {
+    /// Optimistic locking failed - the stream was changed concurrently by someone else
+    Conflict(String),
+    /// The requested resource does not exist
+    NotFound(String),
+    /// The request could not be parsed
+    BadRequest(String),
+    /// The request is well-formed, but semantically invalid
+    Validation(String),
+    /// The event stream for the decider is final/closed, and new events can not be appended to it
+    StreamClosed(String),
+    /// The infrastructure (database, serialization, ...) failed
+    Infrastructure(String),
+}
+
+impl AppError {
+    /// The human-readable explanation specific to this occurrence of the error
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Conflict(message)
+            | AppError::NotFound(message)
+            | AppError::BadRequest(message)
+            | AppError::Validation(message)
+            | AppError::StreamClosed(message)
+            | AppError::Infrastructure(message) => message,
+        }
+    }
+
+    /// The kind of the error - the name of the variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Conflict(_) => "Conflict",
+            AppError::NotFound(_) => "NotFound",
+            AppError::BadRequest(_) => "BadRequest",
+            AppError::Validation(_) => "Validation",
+            AppError::StreamClosed(_) => "StreamClosed",
+            AppError::Infrastructure(_) => "Infrastructure",
+        }
+    }
+}
+
+/// Implement Display for AppError
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+/// Implement Debug for AppError
+impl fmt::Debug for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Conflict(message) => write!(f, "Conflict: {message}"),
+            AppError::NotFound(message) => write!(f, "NotFound: {message}"),
+            AppError::BadRequest(message) => write!(f, "BadRequest: {message}"),
+            AppError::Validation(message) => write!(f, "Validation: {message}"),
+            AppError::StreamClosed(message) => write!(f, "StreamClosed: {message}"),
+            AppError::Infrastructure(message) => write!(f, "Infrastructure: {message}"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(value: serde_json::Error) -> Self {
+        AppError::Infrastructure(value.to_string())
+    }
+}
+
+impl From<uuid::Error> for AppError {
+    fn from(value: uuid::Error) -> Self {
+        AppError::BadRequest(value.to_string())
+    }
+}
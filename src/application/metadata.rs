@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// Metadata of the command that is currently being handled by the task
+    static COMMAND_METADATA: CommandMetadata;
+}
+
+/// Command metadata - the envelope of the command, persisted alongside each event the command produced
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CommandMetadata {
+    /// Unique ID of the command
+    pub command_id: Uuid,
+    /// ID of the whole flow this command belongs to - shared by all the commands and events of the flow
+    pub correlation_id: Uuid,
+    /// ID of the message (command or event) that caused this command - `None` for the flow origin
+    pub causation_id: Option<Uuid>,
+    /// Who issued the command
+    pub actor: String,
+    /// When the command was issued
+    pub timestamp: DateTime<Utc>,
+    /// W3C trace context of the span that handled the command - the reactions to its events continue the same trace
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub trace_context: HashMap<String, String>,
+}
+
+impl CommandMetadata {
+    /// Metadata of a new command that starts a new flow
+    pub fn new(actor: &str) -> Self {
+        let command_id = Uuid::new_v4();
+        CommandMetadata {
+            command_id,
+            correlation_id: command_id,
+            causation_id: None,
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+            trace_context: HashMap::new(),
+        }
+    }
+
+    /// Metadata of a new command caused by the message `causation_id`, within the same flow
+    pub fn caused_by(&self, causation_id: Uuid, actor: &str) -> Self {
+        CommandMetadata {
+            command_id: Uuid::new_v4(),
+            correlation_id: self.correlation_id,
+            causation_id: Some(causation_id),
+            actor: actor.to_string(),
+            timestamp: Utc::now(),
+            trace_context: HashMap::new(),
+        }
+    }
+
+    /// Metadata of the command within the trace of the `trace_context`
+    pub fn with_trace_context(self, trace_context: HashMap<String, String>) -> Self {
+        CommandMetadata {
+            trace_context,
+            ..self
+        }
+    }
+
+    /// Metadata of the command currently being handled by the task, if any
+    pub fn current() -> Option<CommandMetadata> {
+        COMMAND_METADATA.try_with(|metadata| metadata.clone()).ok()
+    }
+
+    /// Run the `future` (command handling) with this metadata as the current command metadata
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        COMMAND_METADATA.scope(self, future).await
+    }
+}
@@ -1 +1,4 @@
 pub mod api;
+pub mod error;
+pub mod metadata;
+pub mod retry;
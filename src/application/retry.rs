@@ -0,0 +1,124 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+use crate::application::error::AppError;
+
+/// Retry policy for the command handling - applied on optimistic locking conflicts only
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every next retry
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Run the command handling `operation` and retry it while it fails with `AppError::Conflict`.
+    /// Every attempt of the `operation` is expected to re-fetch the events and re-run the decider (`EventSourcedAggregate::handle` does exactly that).
+    pub async fn retry_on_conflict<T, F, Fut>(
+        &self,
+        name: &str,
+        operation: F,
+    ) -> Result<T, AppError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(AppError::Conflict(message)) if attempt < self.max_attempts => {
+                    let delay = self.backoff * 2u32.saturating_pow(attempt - 1);
+                    tracing::warn!(
+                        command = name,
+                        attempt,
+                        max_attempts = self.max_attempts,
+                        "Optimistic locking conflict, retrying in {delay:?}: {message}"
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(error) => {
+                    if attempt > 1 {
+                        tracing::error!(
+                            command = name,
+                            attempt,
+                            "Command failed after retries: {error}"
+                        );
+                    }
+                    return Err(error);
+                }
+                Ok(result) => {
+                    if attempt > 1 {
+                        tracing::info!(command = name, attempt, "Command succeeded after retries");
+                    }
+                    return Ok(result);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+/// Tests for the retry policy
+mod retry_tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use crate::application::error::AppError;
+    use crate::application::retry::RetryPolicy;
+
+    #[tokio::test]
+    async fn test() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(1),
+        };
+
+        // Conflict is retried until the operation succeeds
+        let attempts = AtomicU32::new(0);
+        let result = policy
+            .retry_on_conflict("test", || async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(AppError::Conflict("conflict".to_string())),
+                    _ => Ok("done"),
+                }
+            })
+            .await;
+        assert_eq!(result, Ok("done"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        // Conflict is retried at most `max_attempts` times
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), AppError> = policy
+            .retry_on_conflict("test", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(AppError::Conflict("conflict".to_string()))
+            })
+            .await;
+        assert_eq!(result, Err(AppError::Conflict("conflict".to_string())));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // Other errors are not retried
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), AppError> = policy
+            .retry_on_conflict("test", || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(AppError::Infrastructure("down".to_string()))
+            })
+            .await;
+        assert_eq!(result, Err(AppError::Infrastructure("down".to_string())));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
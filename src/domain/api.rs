@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use fmodel_rust::{Identifier, Sum};
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use uuid::Uuid;
@@ -45,12 +47,125 @@ impl fmt::Display for Reason {
     }
 }
 
+/// The currency of the money - ISO 4217 alphabetic code, e.g. `EUR`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(try_from = "String")]
+pub struct Currency(String);
+impl Currency {
+    /// The number of the minor units (decimal places) of the currency, e.g. 2 for EUR (cents), 0 for JPY
+    pub fn minor_units(&self) -> u32 {
+        match self.0.as_str() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+            | "UGX" | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            _ => 2,
+        }
+    }
+}
+/// The currency of the amounts recorded as plain numbers, before the currency was introduced
+impl Default for Currency {
+    fn default() -> Self {
+        Currency("EUR".to_string())
+    }
+}
+impl TryFrom<String> for Currency {
+    type Error = String;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        match code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) {
+            true => Ok(Currency(code)),
+            false => Err(format!("Invalid ISO 4217 currency code: {code}")),
+        }
+    }
+}
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The exact decimal amount of money in the currency.
+/// Serialized as `{"amount": "10.90", "currency": "EUR"}` - the amount is a string, so it round-trips exactly.
+/// The amount recorded as a plain number (e.g. `10.9`), before the currency was introduced, is read as the amount in the default currency.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
-pub struct Money(pub f64);
+#[serde(from = "MoneyRecord")]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: Currency,
+}
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Money { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Money::new(Decimal::ZERO, currency)
+    }
+
+    /// The money multiplied by the quantity - e.g. the price of the order line item. `None` if the amount overflows
+    pub fn times(&self, quantity: u32) -> Option<Money> {
+        self.multiplied_by(Decimal::from(quantity))
+    }
+
+    /// The money multiplied by the rate - e.g. the tax of the amount, not rounded. `None` if the amount overflows
+    pub fn multiplied_by(&self, rate: Decimal) -> Option<Money> {
+        self.amount
+            .checked_mul(rate)
+            .map(|amount| Money::new(amount, self.currency.to_owned()))
+    }
+
+    /// The sum of the money in the same currency - `None` if the currencies differ, or the amount overflows
+    pub fn checked_add(&self, other: &Money) -> Option<Money> {
+        if self.currency != other.currency {
+            return None;
+        }
+        self.amount
+            .checked_add(other.amount)
+            .map(|amount| Money::new(amount, self.currency.to_owned()))
+    }
+
+    /// The sum of all the money in the `currency` - `None` if any of the currencies differ, or the amount overflows
+    pub fn sum<'a>(
+        currency: &Currency,
+        moneys: impl IntoIterator<Item = &'a Money>,
+    ) -> Option<Money> {
+        moneys
+            .into_iter()
+            .try_fold(Money::zero(currency.to_owned()), |total, money| {
+                total.checked_add(money)
+            })
+    }
+
+    /// The money rounded to the minor units of the currency - the midpoint is rounded away from zero (commercial rounding)
+    pub fn round(&self) -> Money {
+        Money::new(
+            self.amount.round_dp_with_strategy(
+                self.currency.minor_units(),
+                RoundingStrategy::MidpointAwayFromZero,
+            ),
+            self.currency.to_owned(),
+        )
+    }
+}
 impl fmt::Display for Money {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Delegate the formatting to the inner Uuid
-        write!(f, "{}", self.0)
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+/// The serialized forms of the money - the amount in the currency, or the plain amount recorded before the currency was introduced
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneyRecord {
+    Money { amount: Decimal, currency: Currency },
+    Amount(Decimal),
+}
+impl From<MoneyRecord> for Money {
+    fn from(record: MoneyRecord) -> Self {
+        match record {
+            MoneyRecord::Money { amount, currency } => Money::new(amount, currency),
+            MoneyRecord::Amount(amount) => Money::new(amount, Currency::default()),
+        }
     }
 }
 
@@ -140,12 +255,94 @@ pub struct RestaurantMenu {
     pub cuisine: RestaurantMenuCuisine,
 }
 
+/// The line item of the order - the `name` and the `price` are the authoritative ones of the restaurant menu, once the order is placed.
+/// The `price` is `None` for the orders placed before it was recorded.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct OrderLineItem {
     pub id: OrderLineItemId,
     pub quantity: OrderLineItemQuantity,
     pub menu_item_id: MenuItemId,
     pub name: MenuItemName,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Money>,
+}
+
+/// The pricing of the restaurant orders - the rates are fractions of the amount, e.g. `0.20` for 20%.
+/// The service charge is applied to the subtotal, and the tax to the subtotal with the service charge.
+/// Both rates are zero for the restaurants created before the pricing was introduced.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct RestaurantPricing {
+    pub tax_rate: Decimal,
+    pub service_charge_rate: Decimal,
+}
+
+/// The totals of the order, computed from the unit prices of the line items and the restaurant pricing at the time the order was placed
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct OrderTotals {
+    pub subtotal: Money,
+    pub service_charge: Money,
+    pub tax: Money,
+    pub total: Money,
+    pub tax_rate: Decimal,
+    pub service_charge_rate: Decimal,
+}
+
+impl OrderTotals {
+    /// Compute the totals of the line items - every amount is rounded to the minor units of the currency.
+    /// The reason if any of the line items is not priced, the line items are priced in different currencies, or the totals overflow.
+    pub fn compute(
+        line_items: &[OrderLineItem],
+        pricing: &RestaurantPricing,
+    ) -> Result<OrderTotals, Reason> {
+        let prices = line_items
+            .iter()
+            .map(|line_item| {
+                line_item
+                    .price
+                    .as_ref()
+                    .ok_or_else(|| Reason(format!("Line item {} is not priced", line_item.id)))
+            })
+            .collect::<Result<Vec<&Money>, Reason>>()?;
+        let currency = prices
+            .first()
+            .map(|price| price.currency.to_owned())
+            .unwrap_or_default();
+        if prices.iter().any(|price| price.currency != currency) {
+            return Err(Reason(
+                "Line items are priced in different currencies".to_string(),
+            ));
+        }
+        // In the same currency, the money arithmetic fails on overflow only
+        let overflow = || Reason("Order totals are too large".to_string());
+        let line_totals = line_items
+            .iter()
+            .zip(prices)
+            .map(|(line_item, price)| price.times(line_item.quantity.0))
+            .collect::<Option<Vec<Money>>>()
+            .ok_or_else(overflow)?;
+        let subtotal = Money::sum(&currency, &line_totals)
+            .ok_or_else(overflow)?
+            .round();
+        let service_charge = subtotal
+            .multiplied_by(pricing.service_charge_rate)
+            .ok_or_else(overflow)?
+            .round();
+        let tax = subtotal
+            .checked_add(&service_charge)
+            .and_then(|taxed| taxed.multiplied_by(pricing.tax_rate))
+            .ok_or_else(overflow)?
+            .round();
+        let total =
+            Money::sum(&currency, [&subtotal, &service_charge, &tax]).ok_or_else(overflow)?;
+        Ok(OrderTotals {
+            subtotal,
+            service_charge,
+            tax,
+            total,
+            tax_rate: pricing.tax_rate,
+            service_charge_rate: pricing.service_charge_rate,
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
@@ -154,63 +351,222 @@ pub enum OrderStatus {
     Prepared,
     Cancelled,
     Rejected,
+    Accepted,
+    InPreparation,
+    ReadyForPickup,
+    PickedUp,
+    Delivered,
+}
+
+impl OrderStatus {
+    /// The order lifecycle state machine - can the order in this status transition to the `next` status:
+    /// `Created` -> `Accepted` -> `InPreparation` -> `Prepared` -> `ReadyForPickup` -> `PickedUp` -> `Delivered`.
+    /// The created order can also be cancelled or rejected. It is prepared only once accepted and in preparation - there is no shortcut.
+    pub fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        matches!(
+            (self, next),
+            (OrderStatus::Created, OrderStatus::Accepted)
+                | (OrderStatus::Created, OrderStatus::Cancelled)
+                | (OrderStatus::Created, OrderStatus::Rejected)
+                | (OrderStatus::Accepted, OrderStatus::InPreparation)
+                | (OrderStatus::InPreparation, OrderStatus::Prepared)
+                | (OrderStatus::Prepared, OrderStatus::ReadyForPickup)
+                | (OrderStatus::ReadyForPickup, OrderStatus::PickedUp)
+                | (OrderStatus::PickedUp, OrderStatus::Delivered)
+        )
+    }
+}
+
+/// The transition of the order to the status - `at` is `None` for the transitions recorded before they were timestamped
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct OrderTransition {
+    pub status: OrderStatus,
+    pub at: Option<DateTime<Utc>>,
 }
 
 // ########################################################
 // ####################### COMMANDS #######################
 // ########################################################
 /// Intent/Command to create a new restaurant
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct CreateRestaurant {
     pub identifier: RestaurantId,
     pub name: RestaurantName,
     pub menu: RestaurantMenu,
+    #[serde(default)]
+    pub pricing: RestaurantPricing,
 }
 
 /// Intent/Command to change the menu of a restaurant
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct ChangeRestaurantMenu {
     pub identifier: RestaurantId,
     pub menu: RestaurantMenu,
 }
 
-/// Intent/Command to place an order at a restaurant
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// Intent/Command to change the pricing (tax and service charge rates) of a restaurant - applies to the orders placed afterwards
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ChangeRestaurantPricing {
+    pub identifier: RestaurantId,
+    pub pricing: RestaurantPricing,
+}
+
+/// Intent/Command to place an order at a restaurant - placed when handled (check `RestaurantCommand::stamped`)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct PlaceOrder {
     pub identifier: RestaurantId,
     pub order_identifier: OrderId,
     pub line_items: Vec<OrderLineItem>,
+    #[serde(skip_deserializing)]
+    pub placed_at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to revoke the order placed at a restaurant (with reason) - compensates the order that was not created, or was rejected
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RevokeOrder {
+    pub identifier: RestaurantId,
+    pub order_identifier: OrderId,
+    pub reason: Reason,
 }
 
 /// All possible command variants that could be sent to a restaurant
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(tag = "type")]
 pub enum RestaurantCommand {
     CreateRestaurant(CreateRestaurant),
     ChangeMenu(ChangeRestaurantMenu),
     PlaceOrder(PlaceOrder),
+    RevokeOrder(RevokeOrder),
+    ChangePricing(ChangeRestaurantPricing),
 }
 
 /// Intent/Command to create a new order
+/// The order commands happen `at` the time they are handled (check `OrderCommand::stamped`)
+/// The `totals` are priced by the restaurant, and set by the order saga only - they are never supplied by the client (they are not deserialized)
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct CreateOrder {
     pub identifier: OrderId,
     pub restaurant_identifier: RestaurantId,
     pub line_items: Vec<OrderLineItem>,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+    #[serde(skip_deserializing)]
+    pub totals: Option<OrderTotals>,
+}
+
+/// Intent/Command to accept an order by the restaurant
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AcceptOrder {
+    pub identifier: OrderId,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to start preparing an order
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StartOrderPreparation {
+    pub identifier: OrderId,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
 }
 
 /// Intent/Command to mark an order as prepared
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct MarkOrderAsPrepared {
     pub identifier: OrderId,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to mark an order as ready for pickup
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MarkOrderAsReadyForPickup {
+    pub identifier: OrderId,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to mark an order as picked up
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MarkOrderAsPickedUp {
+    pub identifier: OrderId,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to mark an order as delivered
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MarkOrderAsDelivered {
+    pub identifier: OrderId,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to cancel an order (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct CancelOrder {
+    pub identifier: OrderId,
+    pub reason: Reason,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Intent/Command to reject an order by the restaurant (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RejectOrder {
+    pub identifier: OrderId,
+    pub reason: Reason,
+    #[serde(skip_deserializing)]
+    pub at: Option<DateTime<Utc>>,
 }
 
 /// All possible command variants that could be sent to an order
+/// The `Create` command carries the price snapshot (totals) - commands are short-lived, so it is not boxed
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
 pub enum OrderCommand {
     Create(CreateOrder),
     MarkAsPrepared(MarkOrderAsPrepared),
+    Cancel(CancelOrder),
+    Reject(RejectOrder),
+    Accept(AcceptOrder),
+    StartPreparation(StartOrderPreparation),
+    MarkAsReadyForPickup(MarkOrderAsReadyForPickup),
+    MarkAsPickedUp(MarkOrderAsPickedUp),
+    MarkAsDelivered(MarkOrderAsDelivered),
+}
+
+impl RestaurantCommand {
+    /// Stamp the command with the time `now` it is handled - the time is never supplied by the client (it is not deserialized).
+    /// The time is stamped after the idempotency check, so that the repeated command is the same command.
+    pub fn stamped(mut self, now: DateTime<Utc>) -> Self {
+        if let RestaurantCommand::PlaceOrder(PlaceOrder { placed_at, .. }) = &mut self {
+            *placed_at = Some(now);
+        }
+        self
+    }
+}
+
+impl OrderCommand {
+    /// Stamp the command with the time `now` it is handled - the time is never supplied by the client (it is not deserialized).
+    /// The time is stamped after the idempotency check, so that the repeated command is the same command.
+    pub fn stamped(mut self, now: DateTime<Utc>) -> Self {
+        match &mut self {
+            OrderCommand::Create(CreateOrder { at, .. })
+            | OrderCommand::MarkAsPrepared(MarkOrderAsPrepared { at, .. })
+            | OrderCommand::Cancel(CancelOrder { at, .. })
+            | OrderCommand::Reject(RejectOrder { at, .. })
+            | OrderCommand::Accept(AcceptOrder { at, .. })
+            | OrderCommand::StartPreparation(StartOrderPreparation { at, .. })
+            | OrderCommand::MarkAsReadyForPickup(MarkOrderAsReadyForPickup { at, .. })
+            | OrderCommand::MarkAsPickedUp(MarkOrderAsPickedUp { at, .. })
+            | OrderCommand::MarkAsDelivered(MarkOrderAsDelivered { at, .. }) => {
+                *at = Some(now);
+            }
+        }
+        self
+    }
 }
 
 // ########################################################
@@ -223,6 +579,8 @@ pub struct RestaurantCreated {
     pub identifier: RestaurantId,
     pub name: RestaurantName,
     pub menu: RestaurantMenu,
+    #[serde(default)]
+    pub pricing: RestaurantPricing,
 }
 
 /// Fact/Event that a restaurant was not created (with reason)
@@ -231,6 +589,8 @@ pub struct RestaurantNotCreated {
     pub identifier: RestaurantId,
     pub name: RestaurantName,
     pub menu: RestaurantMenu,
+    #[serde(default)]
+    pub pricing: RestaurantPricing,
     pub reason: Reason,
 }
 
@@ -249,12 +609,31 @@ pub struct RestaurantMenuNotChanged {
     pub reason: Reason,
 }
 
-/// Fact/Event that an order was placed
+/// Fact/Event that a restaurant's pricing was changed
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RestaurantPricingChanged {
+    pub identifier: RestaurantId,
+    pub pricing: RestaurantPricing,
+}
+
+/// Fact/Event that a restaurant's pricing was not changed (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RestaurantPricingNotChanged {
+    pub identifier: RestaurantId,
+    pub pricing: RestaurantPricing,
+    pub reason: Reason,
+}
+
+/// Fact/Event that an order was placed - `placed_at` and `totals` are `None` for the orders placed before they were recorded
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct OrderPlaced {
     pub identifier: RestaurantId,
     pub order_identifier: OrderId,
     pub line_items: Vec<OrderLineItem>,
+    #[serde(default)]
+    pub placed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub totals: Option<OrderTotals>,
 }
 
 /// Fact/Event that an order was not placed (with reason)
@@ -266,6 +645,22 @@ pub struct OrderNotPlaced {
     pub reason: Reason,
 }
 
+/// Fact/Event that an order placed at a restaurant was revoked (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderRevoked {
+    pub identifier: RestaurantId,
+    pub order_identifier: OrderId,
+    pub reason: Reason,
+}
+
+/// Fact/Event that an order placed at a restaurant was not revoked (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotRevoked {
+    pub identifier: RestaurantId,
+    pub order_identifier: OrderId,
+    pub reason: Reason,
+}
+
 /// All possible event variants that could be used to update a restaurant
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(tag = "type")]
@@ -276,15 +671,25 @@ pub enum RestaurantEvent {
     MenuNotChanged(RestaurantMenuNotChanged),
     OrderPlaced(OrderPlaced),
     OrderNotPlaced(OrderNotPlaced),
+    OrderRevoked(OrderRevoked),
+    OrderNotRevoked(OrderNotRevoked),
+    PricingChanged(RestaurantPricingChanged),
+    PricingNotChanged(RestaurantPricingNotChanged),
 }
 
 /// Fact/Event that an order was created
+/// The order transitions to the status `at` the time - `None` for the events recorded before the transitions were timestamped
+/// The `totals` are the snapshot taken when the order was placed - `None` for the orders placed before they were recorded
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct OrderCreated {
     pub identifier: OrderId,
     pub restaurant_identifier: RestaurantId,
     pub status: OrderStatus,
     pub line_items: Vec<OrderLineItem>,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub totals: Option<OrderTotals>,
 }
 
 /// Fact/Event that an order was not created (with reason)
@@ -296,11 +701,45 @@ pub struct OrderNotCreated {
     pub reason: Reason,
 }
 
+/// Fact/Event that an order was accepted by the restaurant
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderAccepted {
+    pub identifier: OrderId,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that an order was not accepted (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotAccepted {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
+/// Fact/Event that the preparation of an order started
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderPreparationStarted {
+    pub identifier: OrderId,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that the preparation of an order did not start (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderPreparationNotStarted {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
 /// Fact/Event that an order was prepared
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct OrderPrepared {
     pub identifier: OrderId,
     pub status: OrderStatus,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
 }
 
 /// Fact/Event that an order was not prepared (with reason)
@@ -310,6 +749,89 @@ pub struct OrderNotPrepared {
     pub reason: Reason,
 }
 
+/// Fact/Event that an order is ready for pickup
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderReadyForPickup {
+    pub identifier: OrderId,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that an order is not ready for pickup (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotReadyForPickup {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
+/// Fact/Event that an order was picked up
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderPickedUp {
+    pub identifier: OrderId,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that an order was not picked up (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotPickedUp {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
+/// Fact/Event that an order was delivered
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderDelivered {
+    pub identifier: OrderId,
+    pub status: OrderStatus,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that an order was not delivered (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotDelivered {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
+/// Fact/Event that an order was cancelled (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderCancelled {
+    pub identifier: OrderId,
+    pub status: OrderStatus,
+    pub reason: Reason,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that an order was not cancelled (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotCancelled {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
+/// Fact/Event that an order was rejected by the restaurant (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderRejected {
+    pub identifier: OrderId,
+    pub restaurant_identifier: RestaurantId,
+    pub status: OrderStatus,
+    pub reason: Reason,
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Fact/Event that an order was not rejected (with reason)
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OrderNotRejected {
+    pub identifier: OrderId,
+    pub reason: Reason,
+}
+
 /// All possible event variants that could be used to update an order
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(tag = "type")]
@@ -318,6 +840,20 @@ pub enum OrderEvent {
     NotCreated(OrderNotCreated),
     Prepared(OrderPrepared),
     NotPrepared(OrderNotPrepared),
+    Cancelled(OrderCancelled),
+    NotCancelled(OrderNotCancelled),
+    Rejected(OrderRejected),
+    NotRejected(OrderNotRejected),
+    Accepted(OrderAccepted),
+    NotAccepted(OrderNotAccepted),
+    PreparationStarted(OrderPreparationStarted),
+    PreparationNotStarted(OrderPreparationNotStarted),
+    ReadyForPickup(OrderReadyForPickup),
+    NotReadyForPickup(OrderNotReadyForPickup),
+    PickedUp(OrderPickedUp),
+    NotPickedUp(OrderNotPickedUp),
+    Delivered(OrderDelivered),
+    NotDelivered(OrderNotDelivered),
 }
 
 /// All possible event variants that could be used
@@ -333,6 +869,8 @@ impl Identifier for RestaurantCommand {
             RestaurantCommand::CreateRestaurant(command) => command.identifier.to_string(),
             RestaurantCommand::ChangeMenu(command) => command.identifier.to_string(),
             RestaurantCommand::PlaceOrder(command) => command.identifier.to_string(),
+            RestaurantCommand::RevokeOrder(command) => command.identifier.to_string(),
+            RestaurantCommand::ChangePricing(command) => command.identifier.to_string(),
         }
     }
 }
@@ -342,6 +880,13 @@ impl Identifier for OrderCommand {
         match self {
             OrderCommand::Create(command) => command.identifier.to_string(),
             OrderCommand::MarkAsPrepared(command) => command.identifier.to_string(),
+            OrderCommand::Cancel(command) => command.identifier.to_string(),
+            OrderCommand::Reject(command) => command.identifier.to_string(),
+            OrderCommand::Accept(command) => command.identifier.to_string(),
+            OrderCommand::StartPreparation(command) => command.identifier.to_string(),
+            OrderCommand::MarkAsReadyForPickup(command) => command.identifier.to_string(),
+            OrderCommand::MarkAsPickedUp(command) => command.identifier.to_string(),
+            OrderCommand::MarkAsDelivered(command) => command.identifier.to_string(),
         }
     }
 }
@@ -355,6 +900,10 @@ impl Identifier for RestaurantEvent {
             RestaurantEvent::MenuNotChanged(event) => event.identifier.to_string(),
             RestaurantEvent::OrderPlaced(event) => event.identifier.to_string(),
             RestaurantEvent::OrderNotPlaced(event) => event.identifier.to_string(),
+            RestaurantEvent::OrderRevoked(event) => event.identifier.to_string(),
+            RestaurantEvent::OrderNotRevoked(event) => event.identifier.to_string(),
+            RestaurantEvent::PricingChanged(event) => event.identifier.to_string(),
+            RestaurantEvent::PricingNotChanged(event) => event.identifier.to_string(),
         }
     }
 }
@@ -366,6 +915,20 @@ impl Identifier for OrderEvent {
             OrderEvent::NotCreated(event) => event.identifier.to_string(),
             OrderEvent::Prepared(event) => event.identifier.to_string(),
             OrderEvent::NotPrepared(event) => event.identifier.to_string(),
+            OrderEvent::Cancelled(event) => event.identifier.to_string(),
+            OrderEvent::NotCancelled(event) => event.identifier.to_string(),
+            OrderEvent::Rejected(event) => event.identifier.to_string(),
+            OrderEvent::NotRejected(event) => event.identifier.to_string(),
+            OrderEvent::Accepted(event) => event.identifier.to_string(),
+            OrderEvent::NotAccepted(event) => event.identifier.to_string(),
+            OrderEvent::PreparationStarted(event) => event.identifier.to_string(),
+            OrderEvent::PreparationNotStarted(event) => event.identifier.to_string(),
+            OrderEvent::ReadyForPickup(event) => event.identifier.to_string(),
+            OrderEvent::NotReadyForPickup(event) => event.identifier.to_string(),
+            OrderEvent::PickedUp(event) => event.identifier.to_string(),
+            OrderEvent::NotPickedUp(event) => event.identifier.to_string(),
+            OrderEvent::Delivered(event) => event.identifier.to_string(),
+            OrderEvent::NotDelivered(event) => event.identifier.to_string(),
         }
     }
 }
@@ -375,30 +938,80 @@ pub trait DeciderName {
     fn decider_name(&self) -> String;
 }
 
-impl DeciderName for RestaurantEvent {
-    fn decider_name(&self) -> String {
-        match self {
-            RestaurantEvent::Created(_) => "Restaurant".to_string(),
-            RestaurantEvent::NotCreated(_) => "Restaurant".to_string(),
-            RestaurantEvent::MenuChanged(_) => "Restaurant".to_string(),
-            RestaurantEvent::MenuNotChanged(_) => "Restaurant".to_string(),
-            RestaurantEvent::OrderPlaced(_) => "Restaurant".to_string(),
-            RestaurantEvent::OrderNotPlaced(_) => "Restaurant".to_string(),
-        }
-    }
+/// ###### Trait to get the decider name/type of an event #######
+pub trait EventName {
+    fn event_name(&self) -> String;
 }
 
-impl DeciderName for OrderEvent {
-    fn decider_name(&self) -> String {
-        match self {
-            OrderEvent::Created(_) => "Order".to_string(),
-            OrderEvent::NotCreated(_) => "Order".to_string(),
-            OrderEvent::Prepared(_) => "Order".to_string(),
-            OrderEvent::NotPrepared(_) => "Order".to_string(),
+/// ###### Trait to get the catalogue of all the (decider name, event name) pairs of a message type #######
+/// The catalogue is registered in the event store at startup, so that only these events can be appended
+pub trait EventCatalogue {
+    fn event_catalogue() -> Vec<(String, String)>;
+}
+
+/// Implement `DeciderName`, `EventName` and `EventCatalogue` for an event enum from a single table of the variant and event names.
+/// The `EventName` match is exhaustive, so a new variant does not compile until it is named in the table - and the catalogue follows.
+macro_rules! event_names {
+    ($event:ident, $decider:literal, { $($variant:ident => $name:literal),+ $(,)? }) => {
+        impl DeciderName for $event {
+            fn decider_name(&self) -> String {
+                $decider.to_string()
+            }
         }
-    }
+
+        impl EventName for $event {
+            fn event_name(&self) -> String {
+                match self {
+                    $($event::$variant(_) => $name.to_string(),)+
+                }
+            }
+        }
+
+        impl EventCatalogue for $event {
+            fn event_catalogue() -> Vec<(String, String)> {
+                [$($name),+]
+                    .iter()
+                    .map(|event| ($decider.to_string(), event.to_string()))
+                    .collect()
+            }
+        }
+    };
 }
 
+event_names!(RestaurantEvent, "Restaurant", {
+    Created => "RestaurantCreated",
+    NotCreated => "RestaurantNotCreated",
+    MenuChanged => "RestaurantMenuChanged",
+    MenuNotChanged => "RestaurantMenuNotChanged",
+    OrderPlaced => "OrderPlaced",
+    OrderNotPlaced => "OrderNotPlaced",
+    OrderRevoked => "OrderRevoked",
+    OrderNotRevoked => "OrderNotRevoked",
+    PricingChanged => "RestaurantPricingChanged",
+    PricingNotChanged => "RestaurantPricingNotChanged",
+});
+
+event_names!(OrderEvent, "Order", {
+    Created => "OrderCreated",
+    NotCreated => "OrderNotCreated",
+    Prepared => "OrderPrepared",
+    NotPrepared => "OrderNotPrepared",
+    Cancelled => "OrderCancelled",
+    NotCancelled => "OrderNotCancelled",
+    Rejected => "OrderRejected",
+    NotRejected => "OrderNotRejected",
+    Accepted => "OrderAccepted",
+    NotAccepted => "OrderNotAccepted",
+    PreparationStarted => "OrderPreparationStarted",
+    PreparationNotStarted => "OrderPreparationNotStarted",
+    ReadyForPickup => "OrderReadyForPickup",
+    NotReadyForPickup => "OrderNotReadyForPickup",
+    PickedUp => "OrderPickedUp",
+    NotPickedUp => "OrderNotPickedUp",
+    Delivered => "OrderDelivered",
+    NotDelivered => "OrderNotDelivered",
+});
+
 impl DeciderName for Event {
     fn decider_name(&self) -> String {
         match self {
@@ -408,40 +1021,374 @@ impl DeciderName for Event {
     }
 }
 
-/// ###### Trait to get the decider name/type of an event #######
-pub trait EventName {
-    fn event_name(&self) -> String;
-}
-
-impl EventName for RestaurantEvent {
+impl EventName for Event {
     fn event_name(&self) -> String {
         match self {
-            RestaurantEvent::Created(_) => "RestaurantCreated".to_string(),
-            RestaurantEvent::NotCreated(_) => "RestaurantNotCreated".to_string(),
-            RestaurantEvent::MenuChanged(_) => "RestaurantMenuChanged".to_string(),
-            RestaurantEvent::MenuNotChanged(_) => "RestaurantMenuNotChanged".to_string(),
-            RestaurantEvent::OrderPlaced(_) => "OrderPlaced".to_string(),
-            RestaurantEvent::OrderNotPlaced(_) => "OrderNotPlaced".to_string(),
+            Event::First(event) => event.event_name(),
+            Event::Second(event) => event.event_name(),
         }
     }
 }
 
-impl EventName for OrderEvent {
-    fn event_name(&self) -> String {
-        match self {
-            OrderEvent::Created(_) => "OrderCreated".to_string(),
-            OrderEvent::NotCreated(_) => "OrderNotCreated".to_string(),
-            OrderEvent::Prepared(_) => "OrderPrepared".to_string(),
-            OrderEvent::NotPrepared(_) => "OrderNotPrepared".to_string(),
-        }
+impl EventCatalogue for Event {
+    fn event_catalogue() -> Vec<(String, String)> {
+        let mut catalogue = RestaurantEvent::event_catalogue();
+        catalogue.extend(OrderEvent::event_catalogue());
+        catalogue
     }
 }
 
-impl EventName for Event {
-    fn event_name(&self) -> String {
-        match self {
-            Event::First(event) => event.event_name(),
-            Event::Second(event) => event.event_name(),
-        }
+#[cfg(test)]
+/// Tests for the event catalogue and the value objects
+mod api_tests {
+    use rust_decimal::Decimal;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::domain::api::{
+        Currency, DeciderName, Event, EventCatalogue, EventName, MenuId, MenuItemId, MenuItemName,
+        Money, OrderAccepted, OrderCancelled, OrderCommand, OrderCreated, OrderDelivered,
+        OrderEvent, OrderId, OrderLineItem, OrderLineItemId, OrderLineItemQuantity,
+        OrderNotAccepted, OrderNotCancelled, OrderNotCreated, OrderNotDelivered, OrderNotPickedUp,
+        OrderNotPlaced, OrderNotPrepared, OrderNotReadyForPickup, OrderNotRejected,
+        OrderNotRevoked, OrderPickedUp, OrderPlaced, OrderPreparationNotStarted,
+        OrderPreparationStarted, OrderPrepared, OrderReadyForPickup, OrderRejected, OrderRevoked,
+        OrderStatus, OrderTotals, Reason, RestaurantCreated, RestaurantEvent, RestaurantId,
+        RestaurantMenu, RestaurantMenuChanged, RestaurantMenuCuisine, RestaurantMenuNotChanged,
+        RestaurantName, RestaurantNotCreated, RestaurantPricing, RestaurantPricingChanged,
+        RestaurantPricingNotChanged,
+    };
+
+    #[test]
+    fn event_catalogue_test() {
+        // The data
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_identifier =
+            OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+        let name = RestaurantName("Restaurant 1".to_string());
+        let menu = RestaurantMenu {
+            menu_id: MenuId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708210").unwrap()),
+            items: vec![],
+            cuisine: RestaurantMenuCuisine::Vietnamese,
+        };
+        let pricing = RestaurantPricing::default();
+        let reason = Reason("Reason".to_string());
+
+        // One event per variant
+        let events: Vec<Event> = vec![
+            Event::First(RestaurantEvent::Created(RestaurantCreated {
+                identifier: restaurant_identifier.clone(),
+                name: name.clone(),
+                menu: menu.clone(),
+                pricing: pricing.clone(),
+            })),
+            Event::First(RestaurantEvent::NotCreated(RestaurantNotCreated {
+                identifier: restaurant_identifier.clone(),
+                name: name.clone(),
+                menu: menu.clone(),
+                pricing: pricing.clone(),
+                reason: reason.clone(),
+            })),
+            Event::First(RestaurantEvent::MenuChanged(RestaurantMenuChanged {
+                identifier: restaurant_identifier.clone(),
+                menu: menu.clone(),
+            })),
+            Event::First(RestaurantEvent::MenuNotChanged(RestaurantMenuNotChanged {
+                identifier: restaurant_identifier.clone(),
+                menu: menu.clone(),
+                reason: reason.clone(),
+            })),
+            Event::First(RestaurantEvent::OrderPlaced(OrderPlaced {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                line_items: vec![],
+                placed_at: None,
+                totals: None,
+            })),
+            Event::First(RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                line_items: vec![],
+                reason: reason.clone(),
+            })),
+            Event::First(RestaurantEvent::OrderRevoked(OrderRevoked {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::First(RestaurantEvent::OrderNotRevoked(OrderNotRevoked {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::First(RestaurantEvent::PricingChanged(RestaurantPricingChanged {
+                identifier: restaurant_identifier.clone(),
+                pricing: pricing.clone(),
+            })),
+            Event::First(RestaurantEvent::PricingNotChanged(
+                RestaurantPricingNotChanged {
+                    identifier: restaurant_identifier.clone(),
+                    pricing: pricing.clone(),
+                    reason: reason.clone(),
+                },
+            )),
+            Event::Second(OrderEvent::Created(OrderCreated {
+                identifier: order_identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Created,
+                line_items: vec![],
+                at: None,
+                totals: None,
+            })),
+            Event::Second(OrderEvent::NotCreated(OrderNotCreated {
+                identifier: order_identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                line_items: vec![],
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::Prepared(OrderPrepared {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::Prepared,
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotPrepared(OrderNotPrepared {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::Cancelled(OrderCancelled {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::Cancelled,
+                reason: reason.clone(),
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotCancelled(OrderNotCancelled {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::Rejected(OrderRejected {
+                identifier: order_identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Rejected,
+                reason: reason.clone(),
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotRejected(OrderNotRejected {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::Accepted(OrderAccepted {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::Accepted,
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotAccepted(OrderNotAccepted {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::PreparationStarted(OrderPreparationStarted {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::InPreparation,
+                at: None,
+            })),
+            Event::Second(OrderEvent::PreparationNotStarted(
+                OrderPreparationNotStarted {
+                    identifier: order_identifier.clone(),
+                    reason: reason.clone(),
+                },
+            )),
+            Event::Second(OrderEvent::ReadyForPickup(OrderReadyForPickup {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::ReadyForPickup,
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotReadyForPickup(OrderNotReadyForPickup {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::PickedUp(OrderPickedUp {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::PickedUp,
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotPickedUp(OrderNotPickedUp {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+            Event::Second(OrderEvent::Delivered(OrderDelivered {
+                identifier: order_identifier.clone(),
+                status: OrderStatus::Delivered,
+                at: None,
+            })),
+            Event::Second(OrderEvent::NotDelivered(OrderNotDelivered {
+                identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })),
+        ];
+
+        // The catalogue contains exactly the (decider, event) names of all the variants
+        let names: Vec<(String, String)> = events
+            .iter()
+            .map(|event| (event.decider_name(), event.event_name()))
+            .collect();
+        assert_eq!(Event::event_catalogue(), names);
+    }
+
+    #[test]
+    fn money_test() {
+        let eur = Currency::try_from("EUR".to_string()).unwrap();
+        let jpy = Currency::try_from("JPY".to_string()).unwrap();
+        let price = Money::new(Decimal::new(1090, 2), eur.clone());
+
+        // The amount round-trips exactly, with its scale
+        let serialized = serde_json::to_value(&price).unwrap();
+        assert_eq!(serialized, json!({"amount": "10.90", "currency": "EUR"}));
+        let deserialized: Money = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.amount.to_string(), "10.90");
+        assert_eq!(deserialized, price);
+
+        // The plain (float) amount recorded before the currency was introduced is read exactly, in the default currency
+        let legacy: Money = serde_json::from_value(json!(10.9)).unwrap();
+        assert_eq!(
+            legacy,
+            Money::new(Decimal::new(109, 1), Currency::default())
+        );
+
+        // The currency is an ISO 4217 code
+        assert!(
+            serde_json::from_value::<Money>(json!({"amount": "1", "currency": "euro"})).is_err()
+        );
+
+        // Arithmetic
+        let line_total = price.times(3).unwrap();
+        assert_eq!(line_total, Money::new(Decimal::new(3270, 2), eur.clone()));
+        assert_eq!(
+            Money::sum(&eur, [&price, &line_total]),
+            Some(Money::new(Decimal::new(4360, 2), eur.clone()))
+        );
+        assert_eq!(Money::sum(&eur, []), Some(Money::zero(eur.clone())));
+        assert_eq!(
+            price.checked_add(&Money::new(Decimal::ONE, jpy.clone())),
+            None
+        );
+
+        // Overflow is `None` - the arithmetic never panics
+        let max = Money::new(Decimal::MAX, eur.clone());
+        assert_eq!(max.times(2), None);
+        assert_eq!(max.multiplied_by(Decimal::new(15, 1)), None);
+        assert_eq!(max.checked_add(&price), None);
+        assert_eq!(Money::sum(&eur, [&max, &max]), None);
+        assert_eq!(max.times(1), Some(max.clone()));
+
+        // Rounding to the minor units of the currency - the midpoint away from zero
+        assert_eq!(
+            Money::new(Decimal::new(10125, 3), eur.clone()).round(),
+            Money::new(Decimal::new(1013, 2), eur.clone())
+        );
+        assert_eq!(
+            Money::new(Decimal::new(-10125, 3), eur.clone()).round(),
+            Money::new(Decimal::new(-1013, 2), eur.clone())
+        );
+        assert_eq!(
+            Money::new(Decimal::new(1005, 1), jpy.clone()).round(),
+            Money::new(Decimal::new(101, 0), jpy)
+        );
+    }
+
+    #[test]
+    fn order_totals_test() {
+        let eur = Currency::try_from("EUR".to_string()).unwrap();
+        let line_item = |quantity: u32, price: Option<Money>| OrderLineItem {
+            id: OrderLineItemId(Uuid::new_v4()),
+            quantity: OrderLineItemQuantity(quantity),
+            menu_item_id: MenuItemId(Uuid::new_v4()),
+            name: MenuItemName("Item".to_string()),
+            price,
+        };
+        let line_items = vec![
+            line_item(3, Some(Money::new(Decimal::new(1090, 2), eur.clone()))),
+            line_item(1, Some(Money::new(Decimal::new(499, 2), eur.clone()))),
+        ];
+        // 10% service charge, 20% tax
+        let pricing = RestaurantPricing {
+            tax_rate: Decimal::new(20, 2),
+            service_charge_rate: Decimal::new(10, 2),
+        };
+
+        // 3 x 10.90 + 4.99 = 37.69; service charge 3.769 -> 3.77; tax (37.69 + 3.77) x 0.20 = 8.292 -> 8.29
+        assert_eq!(
+            OrderTotals::compute(&line_items, &pricing),
+            Ok(OrderTotals {
+                subtotal: Money::new(Decimal::new(3769, 2), eur.clone()),
+                service_charge: Money::new(Decimal::new(377, 2), eur.clone()),
+                tax: Money::new(Decimal::new(829, 2), eur.clone()),
+                total: Money::new(Decimal::new(4975, 2), eur.clone()),
+                tax_rate: Decimal::new(20, 2),
+                service_charge_rate: Decimal::new(10, 2),
+            })
+        );
+
+        // No taxes and service charges by default
+        let totals = OrderTotals::compute(&line_items, &RestaurantPricing::default()).unwrap();
+        assert_eq!(totals.total, totals.subtotal);
+
+        // The order of nothing costs nothing
+        assert_eq!(
+            OrderTotals::compute(&[], &pricing).map(|totals| totals.total),
+            Ok(Money::zero(Currency::default()))
+        );
+
+        // The totals can not be computed without the prices, in the different currencies, or if they overflow
+        let unpriced = line_item(1, None);
+        assert_eq!(
+            OrderTotals::compute(std::slice::from_ref(&unpriced), &pricing),
+            Err(Reason(format!("Line item {} is not priced", unpriced.id)))
+        );
+        let jpy = Currency::try_from("JPY".to_string()).unwrap();
+        assert_eq!(
+            OrderTotals::compute(
+                &[
+                    line_items[0].clone(),
+                    line_item(1, Some(Money::new(Decimal::new(500, 0), jpy)))
+                ],
+                &pricing
+            ),
+            Err(Reason(
+                "Line items are priced in different currencies".to_string()
+            ))
+        );
+        assert_eq!(
+            OrderTotals::compute(
+                &[line_item(2, Some(Money::new(Decimal::MAX, eur.clone())))],
+                &pricing
+            ),
+            Err(Reason("Order totals are too large".to_string()))
+        );
+    }
+
+    #[test]
+    fn client_supplied_command_fields_test() {
+        // The time and the totals of the order are not taken from the client
+        let command: OrderCommand = serde_json::from_value(json!({
+            "type": "Create",
+            "identifier": "02f09a3f-1624-3b1d-8409-44eff7708207",
+            "restaurant_identifier": "02f09a3f-1624-3b1d-8409-44eff7708208",
+            "line_items": [],
+            "at": "2000-01-01T00:00:00Z",
+            "totals": {
+                "subtotal": {"amount": "0.01", "currency": "EUR"},
+                "service_charge": {"amount": "0", "currency": "EUR"},
+                "tax": {"amount": "0", "currency": "EUR"},
+                "total": {"amount": "0.01", "currency": "EUR"},
+                "tax_rate": "0",
+                "service_charge_rate": "0"
+            }
+        }))
+        .unwrap();
+        let OrderCommand::Create(create_order) = command else {
+            panic!("expected the Create command");
+        };
+        assert_eq!(create_order.at, None);
+        assert_eq!(create_order.totals, None);
     }
 }
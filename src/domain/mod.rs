@@ -3,4 +3,5 @@ pub mod order_decider;
 pub mod order_saga;
 pub mod order_view;
 pub mod restaurant_decider;
+pub mod restaurant_saga;
 pub mod restaurant_view;
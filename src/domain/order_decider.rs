@@ -1,8 +1,11 @@
 use fmodel_rust::decider::Decider;
 
 use crate::domain::api::{
-    OrderCommand, OrderCreated, OrderEvent, OrderId, OrderLineItem, OrderNotCreated,
-    OrderNotPrepared, OrderPrepared, OrderStatus, Reason, RestaurantId,
+    OrderAccepted, OrderCancelled, OrderCommand, OrderCreated, OrderDelivered, OrderEvent, OrderId,
+    OrderLineItem, OrderNotAccepted, OrderNotCancelled, OrderNotCreated, OrderNotDelivered,
+    OrderNotPickedUp, OrderNotPrepared, OrderNotReadyForPickup, OrderNotRejected, OrderPickedUp,
+    OrderPreparationNotStarted, OrderPreparationStarted, OrderPrepared, OrderReadyForPickup,
+    OrderRejected, OrderStatus, Reason, RestaurantId,
 };
 
 /// The state of the Order is represented by this struct. It belongs to the Domain layer.
@@ -17,6 +20,18 @@ pub struct Order {
 /// A convenient type alias for the Order decider
 pub type OrderDecider<'a> = Decider<'a, OrderCommand, Option<Order>, OrderEvent>;
 
+/// Transition the order to the `next` status - the order, or the reason why it can not transition (see `OrderStatus::can_transition_to`)
+fn transition<'s>(state: &'s Option<Order>, next: &OrderStatus) -> Result<&'s Order, Reason> {
+    match state {
+        Some(order) if order.status.can_transition_to(next) => Ok(order),
+        Some(order) => Err(Reason(format!(
+            "Order can not transition from {:?} to {:?}",
+            order.status, next
+        ))),
+        None => Err(Reason("Order does not exist".to_string())),
+    }
+}
+
 /// Decider is a datatype/struct that represents the main decision-making algorithm. It belongs to the Domain layer.
 pub fn order_decider<'a>() -> OrderDecider<'a> {
     Decider {
@@ -37,25 +52,118 @@ pub fn order_decider<'a>() -> OrderDecider<'a> {
                         restaurant_identifier: command.restaurant_identifier.to_owned(),
                         status: OrderStatus::Created,
                         line_items: command.line_items.to_owned(),
+                        at: command.at,
+                        totals: command.totals.to_owned(),
                     })])
                 }
             }
+            OrderCommand::Accept(command) => match transition(state, &OrderStatus::Accepted) {
+                Ok(_) => Ok(vec![OrderEvent::Accepted(OrderAccepted {
+                    identifier: command.identifier.to_owned(),
+                    status: OrderStatus::Accepted,
+                    at: command.at,
+                })]),
+                Err(reason) => Ok(vec![OrderEvent::NotAccepted(OrderNotAccepted {
+                    identifier: command.identifier.to_owned(),
+                    reason,
+                })]),
+            },
+            OrderCommand::StartPreparation(command) => {
+                match transition(state, &OrderStatus::InPreparation) {
+                    Ok(_) => Ok(vec![OrderEvent::PreparationStarted(
+                        OrderPreparationStarted {
+                            identifier: command.identifier.to_owned(),
+                            status: OrderStatus::InPreparation,
+                            at: command.at,
+                        },
+                    )]),
+                    Err(reason) => Ok(vec![OrderEvent::PreparationNotStarted(
+                        OrderPreparationNotStarted {
+                            identifier: command.identifier.to_owned(),
+                            reason,
+                        },
+                    )]),
+                }
+            }
             OrderCommand::MarkAsPrepared(command) => {
-                if state
-                    .clone()
-                    .is_some_and(|s| OrderStatus::Created == s.status)
-                {
-                    Ok(vec![OrderEvent::Prepared(OrderPrepared {
+                match transition(state, &OrderStatus::Prepared) {
+                    Ok(_) => Ok(vec![OrderEvent::Prepared(OrderPrepared {
                         identifier: command.identifier.to_owned(),
                         status: OrderStatus::Prepared,
-                    })])
-                } else {
-                    Ok(vec![OrderEvent::NotPrepared(OrderNotPrepared {
+                        at: command.at,
+                    })]),
+                    Err(reason) => Ok(vec![OrderEvent::NotPrepared(OrderNotPrepared {
                         identifier: command.identifier.to_owned(),
-                        reason: Reason("Order in the wrong status previously".to_string()),
-                    })])
+                        reason,
+                    })]),
+                }
+            }
+            OrderCommand::MarkAsReadyForPickup(command) => {
+                match transition(state, &OrderStatus::ReadyForPickup) {
+                    Ok(_) => Ok(vec![OrderEvent::ReadyForPickup(OrderReadyForPickup {
+                        identifier: command.identifier.to_owned(),
+                        status: OrderStatus::ReadyForPickup,
+                        at: command.at,
+                    })]),
+                    Err(reason) => Ok(vec![OrderEvent::NotReadyForPickup(
+                        OrderNotReadyForPickup {
+                            identifier: command.identifier.to_owned(),
+                            reason,
+                        },
+                    )]),
+                }
+            }
+            OrderCommand::MarkAsPickedUp(command) => {
+                match transition(state, &OrderStatus::PickedUp) {
+                    Ok(_) => Ok(vec![OrderEvent::PickedUp(OrderPickedUp {
+                        identifier: command.identifier.to_owned(),
+                        status: OrderStatus::PickedUp,
+                        at: command.at,
+                    })]),
+                    Err(reason) => Ok(vec![OrderEvent::NotPickedUp(OrderNotPickedUp {
+                        identifier: command.identifier.to_owned(),
+                        reason,
+                    })]),
                 }
             }
+            OrderCommand::MarkAsDelivered(command) => {
+                match transition(state, &OrderStatus::Delivered) {
+                    Ok(_) => Ok(vec![OrderEvent::Delivered(OrderDelivered {
+                        identifier: command.identifier.to_owned(),
+                        status: OrderStatus::Delivered,
+                        at: command.at,
+                    })]),
+                    Err(reason) => Ok(vec![OrderEvent::NotDelivered(OrderNotDelivered {
+                        identifier: command.identifier.to_owned(),
+                        reason,
+                    })]),
+                }
+            }
+            OrderCommand::Cancel(command) => match transition(state, &OrderStatus::Cancelled) {
+                Ok(_) => Ok(vec![OrderEvent::Cancelled(OrderCancelled {
+                    identifier: command.identifier.to_owned(),
+                    status: OrderStatus::Cancelled,
+                    reason: command.reason.to_owned(),
+                    at: command.at,
+                })]),
+                Err(reason) => Ok(vec![OrderEvent::NotCancelled(OrderNotCancelled {
+                    identifier: command.identifier.to_owned(),
+                    reason,
+                })]),
+            },
+            OrderCommand::Reject(command) => match transition(state, &OrderStatus::Rejected) {
+                Ok(order) => Ok(vec![OrderEvent::Rejected(OrderRejected {
+                    identifier: command.identifier.to_owned(),
+                    restaurant_identifier: order.restaurant_identifier.to_owned(),
+                    status: OrderStatus::Rejected,
+                    reason: command.reason.to_owned(),
+                    at: command.at,
+                })]),
+                Err(reason) => Ok(vec![OrderEvent::NotRejected(OrderNotRejected {
+                    identifier: command.identifier.to_owned(),
+                    reason,
+                })]),
+            },
         }),
         // Evolve the state based on the current state and the event
         // Exhaustive pattern matching on the event
@@ -66,16 +174,28 @@ pub fn order_decider<'a>() -> OrderDecider<'a> {
                 status: event.status.to_owned(),
                 line_items: event.line_items.to_owned(),
             }),
-            // On error event we choose NOT TO change the state of the Order, for example.
-            OrderEvent::NotCreated(..) => state.clone(),
-            OrderEvent::Prepared(event) => state.clone().map(|s| Order {
-                identifier: event.identifier.to_owned(),
-                restaurant_identifier: s.restaurant_identifier,
-                status: event.status.to_owned(),
-                line_items: s.line_items,
+            // The order transitions to the status of the event
+            OrderEvent::Accepted(OrderAccepted { status, .. })
+            | OrderEvent::PreparationStarted(OrderPreparationStarted { status, .. })
+            | OrderEvent::Prepared(OrderPrepared { status, .. })
+            | OrderEvent::ReadyForPickup(OrderReadyForPickup { status, .. })
+            | OrderEvent::PickedUp(OrderPickedUp { status, .. })
+            | OrderEvent::Delivered(OrderDelivered { status, .. })
+            | OrderEvent::Cancelled(OrderCancelled { status, .. })
+            | OrderEvent::Rejected(OrderRejected { status, .. }) => state.clone().map(|s| Order {
+                status: status.to_owned(),
+                ..s
             }),
             // On error event we choose NOT TO change the state of the Order, for example.
-            OrderEvent::NotPrepared(..) => state.clone(),
+            OrderEvent::NotCreated(..)
+            | OrderEvent::NotAccepted(..)
+            | OrderEvent::PreparationNotStarted(..)
+            | OrderEvent::NotPrepared(..)
+            | OrderEvent::NotReadyForPickup(..)
+            | OrderEvent::NotPickedUp(..)
+            | OrderEvent::NotDelivered(..)
+            | OrderEvent::NotCancelled(..)
+            | OrderEvent::NotRejected(..) => state.clone(),
         }),
 
         // The initial state of the decider
@@ -86,13 +206,17 @@ pub fn order_decider<'a>() -> OrderDecider<'a> {
 #[cfg(test)]
 /// Tests for the Order decider
 mod order_decider_tests {
+    use chrono::{TimeZone, Utc};
     use fmodel_rust::specification::DeciderTestSpecification;
     use uuid::Uuid;
 
     use crate::domain::api::{
-        CreateOrder, MarkOrderAsPrepared, MenuItemId, MenuItemName, OrderCommand, OrderCreated,
-        OrderEvent, OrderId, OrderLineItem, OrderLineItemId, OrderLineItemQuantity, OrderPrepared,
-        OrderStatus, RestaurantId,
+        AcceptOrder, CancelOrder, CreateOrder, MarkOrderAsDelivered, MarkOrderAsPickedUp,
+        MarkOrderAsPrepared, MenuItemId, MenuItemName, OrderAccepted, OrderCancelled, OrderCommand,
+        OrderCreated, OrderDelivered, OrderEvent, OrderId, OrderLineItem, OrderLineItemId,
+        OrderLineItemQuantity, OrderNotAccepted, OrderNotCancelled, OrderNotPickedUp,
+        OrderNotPrepared, OrderNotRejected, OrderPickedUp, OrderPreparationStarted, OrderPrepared,
+        OrderReadyForPickup, OrderRejected, OrderStatus, Reason, RejectOrder, RestaurantId,
     };
     use crate::domain::order_decider::{order_decider, Order};
 
@@ -111,12 +235,16 @@ mod order_decider_tests {
             name: MenuItemName("Item 1".to_string()),
             quantity: OrderLineItemQuantity(1),
             menu_item_id,
+            price: None,
         }];
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
 
         let create_order_command: OrderCommand = OrderCommand::Create(CreateOrder {
             identifier: identifier.clone(),
             restaurant_identifier: restaurant_identifier.clone(),
             line_items: line_items.clone(),
+            at: Some(at),
+            totals: None,
         });
 
         // ### EventSourced flavour ### - Test the decider: given EVENTS, when COMMAND, then NEW EVENTS
@@ -129,6 +257,8 @@ mod order_decider_tests {
                 restaurant_identifier: restaurant_identifier.clone(),
                 status: OrderStatus::Created,
                 line_items: line_items.clone(),
+                at: Some(at),
+                totals: None,
             })]);
 
         // ### StateStored flavour ### - Test the decider: given STATE, when COMMAND, then NEW STATE
@@ -159,26 +289,212 @@ mod order_decider_tests {
             name: MenuItemName("Item 1".to_string()),
             quantity: OrderLineItemQuantity(1),
             menu_item_id,
+            price: None,
         }];
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
 
         let mark_order_as_prepared: OrderCommand =
             OrderCommand::MarkAsPrepared(MarkOrderAsPrepared {
                 identifier: identifier.clone(),
+                at: Some(at),
             });
 
+        let created = OrderEvent::Created(OrderCreated {
+            identifier: identifier.clone(),
+            restaurant_identifier: restaurant_identifier.clone(),
+            status: OrderStatus::Created,
+            line_items: line_items.clone(),
+            at: Some(at),
+            totals: None,
+        });
+
         // ### EventSourced flavour ### - Test the decider: given EVENTS, when COMMAND, then NEW EVENTS
         DeciderTestSpecification::default()
             .for_decider(self::order_decider()) // Set the decider
-            .given(vec![OrderEvent::Created(OrderCreated {
+            .given(vec![
+                created.clone(),
+                OrderEvent::Accepted(OrderAccepted {
+                    identifier: identifier.clone(),
+                    status: OrderStatus::Accepted,
+                    at: Some(at),
+                }),
+                OrderEvent::PreparationStarted(OrderPreparationStarted {
+                    identifier: identifier.clone(),
+                    status: OrderStatus::InPreparation,
+                    at: Some(at),
+                }),
+            ]) // the order in preparation
+            .when(mark_order_as_prepared.clone()) // Mark the Order as prepared
+            .then(vec![OrderEvent::Prepared(OrderPrepared {
+                identifier: identifier.clone(),
+                status: OrderStatus::Prepared,
+                at: Some(at),
+            })]);
+
+        // The created order is not prepared - it is accepted and in preparation first
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![created]) // the order not accepted yet
+            .when(mark_order_as_prepared.clone()) // Mark the Order as prepared
+            .then(vec![OrderEvent::NotPrepared(OrderNotPrepared {
+                identifier: identifier.clone(),
+                reason: Reason("Order can not transition from Created to Prepared".to_string()),
+            })]);
+
+        // ### StateStored flavour ### - Test the decider: given STATE, when COMMAND, then NEW STATE
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given_state(Some(Some(Order {
                 identifier: identifier.clone(),
                 restaurant_identifier: restaurant_identifier.clone(),
-                status: OrderStatus::Created,
+                status: OrderStatus::InPreparation,
                 line_items: line_items.clone(),
-            })]) // no existing events
-            .when(mark_order_as_prepared.clone()) // Create an Order
-            .then(vec![OrderEvent::Prepared(OrderPrepared {
+            }))) // the order in preparation
+            .when(mark_order_as_prepared.clone()) // Mark the Order as prepared
+            .then_state(Some(Order {
                 identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
                 status: OrderStatus::Prepared,
+                line_items: line_items.clone(),
+            }));
+    }
+
+    #[test]
+    fn cancel_order_test() {
+        // The data
+        let identifier = OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_line_item_id =
+            OrderLineItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708209").unwrap());
+        let menu_item_id =
+            MenuItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708210").unwrap());
+        let line_items = vec![OrderLineItem {
+            id: order_line_item_id,
+            name: MenuItemName("Item 1".to_string()),
+            quantity: OrderLineItemQuantity(1),
+            menu_item_id,
+            price: None,
+        }];
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let reason = Reason("Customer changed their mind".to_string());
+
+        let order_created_event = OrderEvent::Created(OrderCreated {
+            identifier: identifier.clone(),
+            restaurant_identifier: restaurant_identifier.clone(),
+            status: OrderStatus::Created,
+            line_items: line_items.clone(),
+            at: Some(at),
+            totals: None,
+        });
+        let cancel_order: OrderCommand = OrderCommand::Cancel(CancelOrder {
+            identifier: identifier.clone(),
+            reason: reason.clone(),
+            at: Some(at),
+        });
+
+        // ### EventSourced flavour ### - Test the decider: given EVENTS, when COMMAND, then NEW EVENTS
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![order_created_event.clone()]) // the order is created
+            .when(cancel_order.clone()) // Cancel the Order
+            .then(vec![OrderEvent::Cancelled(OrderCancelled {
+                identifier: identifier.clone(),
+                status: OrderStatus::Cancelled,
+                reason: reason.clone(),
+                at: Some(at),
+            })]);
+
+        // ### StateStored flavour ### - Test the decider: given STATE, when COMMAND, then NEW STATE
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given_state(Some(Some(Order {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Created,
+                line_items: line_items.clone(),
+            }))) // the order is created
+            .when(cancel_order.clone()) // Cancel the Order
+            .then_state(Some(Order {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Cancelled,
+                line_items: line_items.clone(),
+            }));
+
+        // The prepared order can not be cancelled
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![
+                order_created_event.clone(),
+                OrderEvent::Prepared(OrderPrepared {
+                    identifier: identifier.clone(),
+                    status: OrderStatus::Prepared,
+                    at: Some(at),
+                }),
+            ]) // the order is prepared
+            .when(cancel_order.clone()) // Cancel the Order
+            .then(vec![OrderEvent::NotCancelled(OrderNotCancelled {
+                identifier: identifier.clone(),
+                reason: Reason("Order can not transition from Prepared to Cancelled".to_string()),
+            })]);
+
+        // The order that does not exist can not be cancelled
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![]) // no existing events
+            .when(cancel_order.clone()) // Cancel the Order
+            .then(vec![OrderEvent::NotCancelled(OrderNotCancelled {
+                identifier: identifier.clone(),
+                reason: Reason("Order does not exist".to_string()),
+            })]);
+    }
+
+    #[test]
+    fn reject_order_test() {
+        // The data
+        let identifier = OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_line_item_id =
+            OrderLineItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708209").unwrap());
+        let menu_item_id =
+            MenuItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708210").unwrap());
+        let line_items = vec![OrderLineItem {
+            id: order_line_item_id,
+            name: MenuItemName("Item 1".to_string()),
+            quantity: OrderLineItemQuantity(1),
+            menu_item_id,
+            price: None,
+        }];
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let reason = Reason("Out of stock".to_string());
+
+        let order_created_event = OrderEvent::Created(OrderCreated {
+            identifier: identifier.clone(),
+            restaurant_identifier: restaurant_identifier.clone(),
+            status: OrderStatus::Created,
+            line_items: line_items.clone(),
+            at: Some(at),
+            totals: None,
+        });
+        let reject_order: OrderCommand = OrderCommand::Reject(RejectOrder {
+            identifier: identifier.clone(),
+            reason: reason.clone(),
+            at: Some(at),
+        });
+
+        // ### EventSourced flavour ### - Test the decider: given EVENTS, when COMMAND, then NEW EVENTS
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![order_created_event.clone()]) // the order is created
+            .when(reject_order.clone()) // Reject the Order
+            .then(vec![OrderEvent::Rejected(OrderRejected {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Rejected,
+                reason: reason.clone(),
+                at: Some(at),
             })]);
 
         // ### StateStored flavour ### - Test the decider: given STATE, when COMMAND, then NEW STATE
@@ -189,13 +505,162 @@ mod order_decider_tests {
                 restaurant_identifier: restaurant_identifier.clone(),
                 status: OrderStatus::Created,
                 line_items: line_items.clone(),
-            }))) // no existing state
-            .when(mark_order_as_prepared.clone()) // Create an Order
+            }))) // the order is created
+            .when(reject_order.clone()) // Reject the Order
             .then_state(Some(Order {
                 identifier: identifier.clone(),
                 restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Rejected,
+                line_items: line_items.clone(),
+            }));
+
+        // The cancelled order can not be rejected
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![
+                order_created_event.clone(),
+                OrderEvent::Cancelled(OrderCancelled {
+                    identifier: identifier.clone(),
+                    status: OrderStatus::Cancelled,
+                    reason: Reason("Customer changed their mind".to_string()),
+                    at: Some(at),
+                }),
+            ]) // the order is cancelled
+            .when(reject_order.clone()) // Reject the Order
+            .then(vec![OrderEvent::NotRejected(OrderNotRejected {
+                identifier: identifier.clone(),
+                reason: Reason("Order can not transition from Cancelled to Rejected".to_string()),
+            })]);
+    }
+
+    #[test]
+    fn order_lifecycle_test() {
+        // The data
+        let identifier = OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_line_item_id =
+            OrderLineItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708209").unwrap());
+        let menu_item_id =
+            MenuItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708210").unwrap());
+        let line_items = vec![OrderLineItem {
+            id: order_line_item_id,
+            name: MenuItemName("Item 1".to_string()),
+            quantity: OrderLineItemQuantity(1),
+            menu_item_id,
+            price: None,
+        }];
+        let at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+
+        let order_created_event = OrderEvent::Created(OrderCreated {
+            identifier: identifier.clone(),
+            restaurant_identifier: restaurant_identifier.clone(),
+            status: OrderStatus::Created,
+            line_items: line_items.clone(),
+            at: Some(at),
+            totals: None,
+        });
+        let order_picked_up_events = vec![
+            order_created_event.clone(),
+            OrderEvent::Accepted(OrderAccepted {
+                identifier: identifier.clone(),
+                status: OrderStatus::Accepted,
+                at: Some(at),
+            }),
+            OrderEvent::PreparationStarted(OrderPreparationStarted {
+                identifier: identifier.clone(),
+                status: OrderStatus::InPreparation,
+                at: Some(at),
+            }),
+            OrderEvent::Prepared(OrderPrepared {
+                identifier: identifier.clone(),
                 status: OrderStatus::Prepared,
+                at: Some(at),
+            }),
+            OrderEvent::ReadyForPickup(OrderReadyForPickup {
+                identifier: identifier.clone(),
+                status: OrderStatus::ReadyForPickup,
+                at: Some(at),
+            }),
+            OrderEvent::PickedUp(OrderPickedUp {
+                identifier: identifier.clone(),
+                status: OrderStatus::PickedUp,
+                at: Some(at),
+            }),
+        ];
+
+        // ### EventSourced flavour ### - Test the decider: given EVENTS, when COMMAND, then NEW EVENTS
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![order_created_event.clone()]) // the order is created
+            .when(OrderCommand::Accept(AcceptOrder {
+                identifier: identifier.clone(),
+                at: Some(at),
+            })) // Accept the Order
+            .then(vec![OrderEvent::Accepted(OrderAccepted {
+                identifier: identifier.clone(),
+                status: OrderStatus::Accepted,
+                at: Some(at),
+            })]);
+
+        // The order goes through the whole lifecycle
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(order_picked_up_events.clone()) // the order is picked up
+            .when(OrderCommand::MarkAsDelivered(MarkOrderAsDelivered {
+                identifier: identifier.clone(),
+                at: Some(at),
+            })) // Deliver the Order
+            .then(vec![OrderEvent::Delivered(OrderDelivered {
+                identifier: identifier.clone(),
+                status: OrderStatus::Delivered,
+                at: Some(at),
+            })]);
+
+        // ### StateStored flavour ### - Test the decider: given STATE, when COMMAND, then NEW STATE
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given_state(Some(Some(Order {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::PickedUp,
+                line_items: line_items.clone(),
+            }))) // the order is picked up
+            .when(OrderCommand::MarkAsDelivered(MarkOrderAsDelivered {
+                identifier: identifier.clone(),
+                at: Some(at),
+            })) // Deliver the Order
+            .then_state(Some(Order {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Delivered,
                 line_items: line_items.clone(),
             }));
+
+        // The order can not skip the statuses
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(vec![order_created_event.clone()]) // the order is created
+            .when(OrderCommand::MarkAsPickedUp(MarkOrderAsPickedUp {
+                identifier: identifier.clone(),
+                at: Some(at),
+            })) // Pick up the Order
+            .then(vec![OrderEvent::NotPickedUp(OrderNotPickedUp {
+                identifier: identifier.clone(),
+                reason: Reason("Order can not transition from Created to PickedUp".to_string()),
+            })]);
+
+        // The order can not go back
+        DeciderTestSpecification::default()
+            .for_decider(self::order_decider()) // Set the decider
+            .given(order_picked_up_events.clone()) // the order is picked up
+            .when(OrderCommand::Accept(AcceptOrder {
+                identifier: identifier.clone(),
+                at: Some(at),
+            })) // Accept the Order
+            .then(vec![OrderEvent::NotAccepted(OrderNotAccepted {
+                identifier: identifier.clone(),
+                reason: Reason("Order can not transition from PickedUp to Accepted".to_string()),
+            })]);
     }
 }
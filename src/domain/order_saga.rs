@@ -1,3 +1,4 @@
+use chrono::Utc;
 use fmodel_rust::saga::Saga;
 
 use crate::domain::api::{CreateOrder, OrderCommand, RestaurantEvent};
@@ -15,6 +16,9 @@ pub fn order_saga<'a>() -> OrderSaga<'a> {
                     identifier: event.order_identifier.to_owned(),
                     restaurant_identifier: event.identifier.to_owned(),
                     line_items: event.line_items.to_owned(),
+                    // The order placed before the placement time was recorded is created now
+                    at: Some(event.placed_at.unwrap_or_else(Utc::now)),
+                    totals: event.totals.to_owned(),
                 })]
             }
             RestaurantEvent::OrderNotPlaced(..) => {
@@ -32,6 +36,18 @@ pub fn order_saga<'a>() -> OrderSaga<'a> {
             RestaurantEvent::MenuChanged(..) => {
                 vec![]
             }
+            RestaurantEvent::OrderRevoked(..) => {
+                vec![]
+            }
+            RestaurantEvent::OrderNotRevoked(..) => {
+                vec![]
+            }
+            RestaurantEvent::PricingChanged(..) => {
+                vec![]
+            }
+            RestaurantEvent::PricingNotChanged(..) => {
+                vec![]
+            }
         }),
     }
 }
@@ -39,11 +55,14 @@ pub fn order_saga<'a>() -> OrderSaga<'a> {
 #[cfg(test)]
 /// Tests for the Order saga
 mod order_saga_tests {
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
     use uuid::Uuid;
 
     use crate::domain::api::{
-        CreateOrder, MenuItemId, MenuItemName, OrderCommand, OrderId, OrderLineItem,
-        OrderLineItemId, OrderLineItemQuantity, OrderPlaced, RestaurantEvent, RestaurantId,
+        CreateOrder, Currency, MenuItemId, MenuItemName, Money, OrderCommand, OrderId,
+        OrderLineItem, OrderLineItemId, OrderLineItemQuantity, OrderPlaced, OrderTotals,
+        RestaurantEvent, RestaurantId,
     };
     use crate::domain::order_saga::{order_saga, OrderSaga};
 
@@ -56,6 +75,16 @@ mod order_saga_tests {
             RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
         let menu_item_id =
             MenuItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708210").unwrap());
+        let placed_at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let eur = Currency::try_from("EUR".to_string()).unwrap();
+        let totals = OrderTotals {
+            subtotal: Money::new(Decimal::new(1000, 2), eur.clone()),
+            service_charge: Money::zero(eur.clone()),
+            tax: Money::new(Decimal::new(200, 2), eur.clone()),
+            total: Money::new(Decimal::new(1200, 2), eur.clone()),
+            tax_rate: Decimal::new(20, 2),
+            service_charge_rate: Decimal::ZERO,
+        };
 
         let order_placed_event = RestaurantEvent::OrderPlaced(OrderPlaced {
             identifier: restaurant_identifier.clone(),
@@ -69,7 +98,10 @@ mod order_saga_tests {
                 name: MenuItemName("Item 1".to_string()),
                 quantity: OrderLineItemQuantity(1),
                 menu_item_id: menu_item_id.clone(),
+                price: None,
             }],
+            placed_at: Some(placed_at),
+            totals: Some(totals.clone()),
         });
 
         let create_order_command = OrderCommand::Create(CreateOrder {
@@ -82,7 +114,10 @@ mod order_saga_tests {
                 name: MenuItemName("Item 1".to_string()),
                 quantity: OrderLineItemQuantity(1),
                 menu_item_id: menu_item_id.clone(),
+                price: None,
             }],
+            at: Some(placed_at),
+            totals: Some(totals.clone()),
         });
 
         let commands = (saga.react)(&order_placed_event);
@@ -1,15 +1,25 @@
 use fmodel_rust::view::View;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::api::{OrderEvent, OrderId, OrderLineItem, OrderStatus, RestaurantId};
+use crate::domain::api::{
+    OrderAccepted, OrderCancelled, OrderDelivered, OrderEvent, OrderId, OrderLineItem,
+    OrderPickedUp, OrderPreparationStarted, OrderPrepared, OrderReadyForPickup, OrderRejected,
+    OrderStatus, OrderTotals, OrderTransition, RestaurantId,
+};
 
 /// The state of the Order is represented by this struct. It belongs to the Domain layer.
+/// The `history` of the status transitions is empty for the orders projected before it was recorded.
+/// The `totals` are `None` for the orders placed before the prices were snapshotted.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct OrderViewState {
     pub identifier: OrderId,
     pub restaurant_identifier: RestaurantId,
     pub status: OrderStatus,
     pub line_items: Vec<OrderLineItem>,
+    #[serde(default)]
+    pub history: Vec<OrderTransition>,
+    #[serde(default)]
+    pub totals: Option<OrderTotals>,
 }
 
 /// A convenient type alias for the Order view
@@ -26,18 +36,40 @@ pub fn order_view<'a>() -> OrderView<'a> {
                 restaurant_identifier: event.restaurant_identifier.to_owned(),
                 status: event.status.to_owned(),
                 line_items: event.line_items.to_owned(),
+                history: vec![OrderTransition {
+                    status: event.status.to_owned(),
+                    at: event.at,
+                }],
+                totals: event.totals.to_owned(),
             }),
+            // The order transitions to the status of the event, and the transition is appended to the history
+            OrderEvent::Accepted(OrderAccepted { status, at, .. })
+            | OrderEvent::PreparationStarted(OrderPreparationStarted { status, at, .. })
+            | OrderEvent::Prepared(OrderPrepared { status, at, .. })
+            | OrderEvent::ReadyForPickup(OrderReadyForPickup { status, at, .. })
+            | OrderEvent::PickedUp(OrderPickedUp { status, at, .. })
+            | OrderEvent::Delivered(OrderDelivered { status, at, .. })
+            | OrderEvent::Cancelled(OrderCancelled { status, at, .. })
+            | OrderEvent::Rejected(OrderRejected { status, at, .. }) => {
+                state.clone().map(|mut s| {
+                    s.status = status.to_owned();
+                    s.history.push(OrderTransition {
+                        status: status.to_owned(),
+                        at: at.to_owned(),
+                    });
+                    s
+                })
+            }
             // On error event we choose NOT TO change the state of the Order, for example.
-            OrderEvent::NotCreated(..) => state.clone(),
-
-            OrderEvent::Prepared(event) => state.clone().map(|s| OrderViewState {
-                identifier: event.identifier.to_owned(),
-                restaurant_identifier: s.restaurant_identifier,
-                status: event.status.to_owned(),
-                line_items: s.line_items,
-            }),
-            // On error event we choose NOT TO change the state of the Order, for example.
-            OrderEvent::NotPrepared(..) => state.clone(),
+            OrderEvent::NotCreated(..)
+            | OrderEvent::NotAccepted(..)
+            | OrderEvent::PreparationNotStarted(..)
+            | OrderEvent::NotPrepared(..)
+            | OrderEvent::NotReadyForPickup(..)
+            | OrderEvent::NotPickedUp(..)
+            | OrderEvent::NotDelivered(..)
+            | OrderEvent::NotCancelled(..)
+            | OrderEvent::NotRejected(..) => state.clone(),
         }),
 
         // The initial state of the decider
@@ -48,12 +80,16 @@ pub fn order_view<'a>() -> OrderView<'a> {
 #[cfg(test)]
 /// Tests for the Order view
 mod order_view_tests {
+    use chrono::{TimeZone, Utc};
     use fmodel_rust::specification::ViewTestSpecification;
+    use rust_decimal::Decimal;
     use uuid::Uuid;
 
     use crate::domain::api::{
-        MenuItemId, MenuItemName, OrderCreated, OrderEvent, OrderId, OrderLineItem,
-        OrderLineItemId, OrderLineItemQuantity, OrderPrepared, OrderStatus, RestaurantId,
+        Currency, MenuItemId, MenuItemName, Money, OrderCancelled, OrderCreated, OrderEvent,
+        OrderId, OrderLineItem, OrderLineItemId, OrderLineItemQuantity, OrderNotCancelled,
+        OrderPrepared, OrderStatus, OrderTotals, OrderTransition, Reason, RestaurantId,
+        RestaurantPricing,
     };
     use crate::domain::order_view::{order_view, OrderViewState};
 
@@ -72,14 +108,31 @@ mod order_view_tests {
             name: MenuItemName("Item 1".to_string()),
             quantity: OrderLineItemQuantity(1),
             menu_item_id,
+            price: Some(Money::new(Decimal::new(1000, 2), Currency::default())),
         }];
+        let totals = OrderTotals::compute(
+            &line_items,
+            &RestaurantPricing {
+                tax_rate: Decimal::new(20, 2),
+                service_charge_rate: Decimal::ZERO,
+            },
+        )
+        .ok();
+        let created_at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let changed_at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 15, 0).unwrap();
 
         let order_created_event: OrderEvent = OrderEvent::Created(OrderCreated {
             identifier: identifier.clone(),
             restaurant_identifier: restaurant_identifier.clone(),
             status: OrderStatus::Created,
             line_items: line_items.clone(),
+            at: Some(created_at),
+            totals: totals.clone(),
         });
+        let created = OrderTransition {
+            status: OrderStatus::Created,
+            at: Some(created_at),
+        };
 
         ViewTestSpecification::default()
             .for_view(self::order_view())
@@ -89,11 +142,14 @@ mod order_view_tests {
                 restaurant_identifier: restaurant_identifier.clone(),
                 status: OrderStatus::Created,
                 line_items: line_items.clone(),
+                history: vec![created.clone()],
+                totals: totals.clone(),
             }));
 
         let order_prepared_event: OrderEvent = OrderEvent::Prepared(OrderPrepared {
             identifier: identifier.clone(),
             status: OrderStatus::Prepared,
+            at: Some(changed_at),
         });
         ViewTestSpecification::default()
             .for_view(self::order_view())
@@ -106,6 +162,70 @@ mod order_view_tests {
                 restaurant_identifier: restaurant_identifier.clone(),
                 status: OrderStatus::Prepared,
                 line_items: line_items.clone(),
+                history: vec![
+                    created.clone(),
+                    OrderTransition {
+                        status: OrderStatus::Prepared,
+                        at: Some(changed_at),
+                    },
+                ],
+                totals: totals.clone(),
+            }));
+
+        let order_cancelled_event: OrderEvent = OrderEvent::Cancelled(OrderCancelled {
+            identifier: identifier.clone(),
+            status: OrderStatus::Cancelled,
+            reason: Reason("Customer changed their mind".to_string()),
+            at: Some(changed_at),
+        });
+        ViewTestSpecification::default()
+            .for_view(self::order_view())
+            .given(vec![
+                order_created_event.clone(),
+                order_cancelled_event.clone(),
+                OrderEvent::NotCancelled(OrderNotCancelled {
+                    identifier: identifier.clone(),
+                    reason: Reason(
+                        "Order can not transition from Cancelled to Cancelled".to_string(),
+                    ),
+                }),
+            ])
+            .then(Some(OrderViewState {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Cancelled,
+                line_items: line_items.clone(),
+                history: vec![
+                    created.clone(),
+                    OrderTransition {
+                        status: OrderStatus::Cancelled,
+                        at: Some(changed_at),
+                    },
+                ],
+                totals: totals.clone(),
+            }));
+
+        // The events recorded before the transitions were timestamped and the prices were snapshotted
+        ViewTestSpecification::default()
+            .for_view(self::order_view())
+            .given(vec![OrderEvent::Created(OrderCreated {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Created,
+                line_items: line_items.clone(),
+                at: None,
+                totals: None,
+            })])
+            .then(Some(OrderViewState {
+                identifier: identifier.clone(),
+                restaurant_identifier: restaurant_identifier.clone(),
+                status: OrderStatus::Created,
+                line_items: line_items.clone(),
+                history: vec![OrderTransition {
+                    status: OrderStatus::Created,
+                    at: None,
+                }],
+                totals: None,
             }));
     }
 }
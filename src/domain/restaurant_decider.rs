@@ -1,23 +1,145 @@
 use fmodel_rust::decider::Decider;
+use rust_decimal::Decimal;
 
 use crate::domain::api::{
-    OrderNotPlaced, OrderPlaced, Reason, RestaurantCommand, RestaurantCreated, RestaurantEvent,
-    RestaurantId, RestaurantMenu, RestaurantMenuChanged, RestaurantMenuNotChanged, RestaurantName,
-    RestaurantNotCreated,
+    OrderId, OrderLineItem, OrderNotPlaced, OrderNotRevoked, OrderPlaced, OrderRevoked,
+    OrderTotals, Reason, RestaurantCommand, RestaurantCreated, RestaurantEvent, RestaurantId,
+    RestaurantMenu, RestaurantMenuChanged, RestaurantMenuNotChanged, RestaurantName,
+    RestaurantNotCreated, RestaurantPricing, RestaurantPricingChanged, RestaurantPricingNotChanged,
 };
 
 /// The state of the Restaurant is represented by this struct. It belongs to the Domain layer.
+/// The orders placed at the restaurant, and the ones revoked since, are tracked so that only the placed orders are revoked - once.
 #[derive(Clone, PartialEq, Debug)]
 pub struct Restaurant {
     identifier: RestaurantId,
     name: RestaurantName,
     menu: RestaurantMenu,
+    pricing: RestaurantPricing,
+    placed_orders: Vec<OrderId>,
+    revoked_orders: Vec<OrderId>,
 }
 
 /// A convenient type alias for the Restaurant decider
 pub type RestaurantDecider<'a> =
     Decider<'a, RestaurantCommand, Option<Restaurant>, RestaurantEvent>;
 
+/// Check the line items of the order against the restaurant menu - the line items with the authoritative names and prices of the menu items, or the reason listing every invalid line item (or that there are none)
+fn check_line_items(
+    menu: &RestaurantMenu,
+    line_items: &[OrderLineItem],
+) -> Result<Vec<OrderLineItem>, Reason> {
+    if line_items.is_empty() {
+        return Err(Reason("Order has no line items".to_string()));
+    }
+    let mut checked = Vec::new();
+    let mut invalid = Vec::new();
+    for line_item in line_items {
+        let menu_item = menu
+            .items
+            .iter()
+            .find(|item| item.id == line_item.menu_item_id);
+        match menu_item {
+            None => invalid.push(format!(
+                "Line item {}: menu item {} is not on the menu",
+                line_item.id, line_item.menu_item_id
+            )),
+            Some(_) if line_item.quantity.0 == 0 => invalid.push(format!(
+                "Line item {}: quantity must be greater than zero",
+                line_item.id
+            )),
+            Some(menu_item) => checked.push(OrderLineItem {
+                name: menu_item.name.to_owned(),
+                price: Some(menu_item.price.to_owned()),
+                ..line_item.to_owned()
+            }),
+        }
+    }
+    match invalid.is_empty() {
+        true => Ok(checked),
+        false => Err(Reason(invalid.join("; "))),
+    }
+}
+
+/// Price the order placed at the restaurant - the checked line items and the totals, snapshotting the menu prices and the restaurant pricing at the time the order is placed
+fn price_order(
+    restaurant: &Restaurant,
+    line_items: &[OrderLineItem],
+) -> Result<(Vec<OrderLineItem>, OrderTotals), Reason> {
+    let line_items = check_line_items(&restaurant.menu, line_items)?;
+    let totals = OrderTotals::compute(&line_items, &restaurant.pricing)?;
+    Ok((line_items, totals))
+}
+
+/// The highest price of a menu item - keeps the order totals well within the range of the decimal amounts
+const MAX_MENU_ITEM_PRICE: Decimal = Decimal::from_parts(1_000_000, 0, 0, false, 0);
+
+/// Check the prices of the menu items - not negative, not above `MAX_MENU_ITEM_PRICE`, and all in the same currency, or the reason listing every invalid menu item
+fn check_menu(menu: &RestaurantMenu) -> Result<(), Reason> {
+    let Some(currency) = menu.items.first().map(|item| &item.price.currency) else {
+        return Ok(());
+    };
+    let invalid: Vec<String> = menu
+        .items
+        .iter()
+        .filter_map(|item| {
+            let price = &item.price;
+            if price.amount < Decimal::ZERO {
+                Some(format!(
+                    "Menu item {}: price {price} must not be negative",
+                    item.id
+                ))
+            } else if price.amount > MAX_MENU_ITEM_PRICE {
+                Some(format!(
+                    "Menu item {}: price {price} must not exceed {MAX_MENU_ITEM_PRICE}",
+                    item.id
+                ))
+            } else if price.currency != *currency {
+                Some(format!(
+                    "Menu item {}: price {price} must be in {currency}, as the rest of the menu",
+                    item.id
+                ))
+            } else {
+                None
+            }
+        })
+        .collect();
+    match invalid.is_empty() {
+        true => Ok(()),
+        false => Err(Reason(invalid.join("; "))),
+    }
+}
+
+/// Check the pricing of the restaurant - the rates are fractions between zero and one, or the reason listing every invalid rate
+fn check_pricing(pricing: &RestaurantPricing) -> Result<(), Reason> {
+    let invalid: Vec<String> = [
+        ("Tax rate", pricing.tax_rate),
+        ("Service charge rate", pricing.service_charge_rate),
+    ]
+    .into_iter()
+    .filter(|(_, rate)| *rate < Decimal::ZERO || *rate > Decimal::ONE)
+    .map(|(name, rate)| format!("{name} {rate} must be between 0 and 1"))
+    .collect();
+    match invalid.is_empty() {
+        true => Ok(()),
+        false => Err(Reason(invalid.join("; "))),
+    }
+}
+
+/// Check that the order can be revoked - it was placed at the restaurant, and not revoked already
+fn check_revocable(state: &Option<Restaurant>, order_identifier: &OrderId) -> Result<(), Reason> {
+    match state {
+        None => Err(Reason("Restaurant does not exist".to_string())),
+        Some(restaurant) if !restaurant.placed_orders.contains(order_identifier) => Err(Reason(
+            format!("Order {order_identifier} was not placed at the restaurant"),
+        )),
+        Some(restaurant) if restaurant.revoked_orders.contains(order_identifier) => Err(Reason(
+            format!("Order {order_identifier} is revoked already"),
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
 /// Decider is a datatype/struct that represents the main decision-making algorithm. It belongs to the Domain layer.
 pub fn restaurant_decider<'a>() -> RestaurantDecider<'a> {
     Decider {
@@ -25,51 +147,103 @@ pub fn restaurant_decider<'a>() -> RestaurantDecider<'a> {
         // Exhaustive pattern matching on the command
         decide: Box::new(|command, state| match command {
             RestaurantCommand::CreateRestaurant(command) => {
-                if state.is_some() {
-                    Ok(vec![RestaurantEvent::NotCreated(RestaurantNotCreated {
+                let checked = match state {
+                    Some(_) => Err(Reason("Restaurant already exists".to_string())),
+                    None => check_menu(&command.menu).and(check_pricing(&command.pricing)),
+                };
+                match checked {
+                    Ok(()) => Ok(vec![RestaurantEvent::Created(RestaurantCreated {
                         identifier: command.identifier.to_owned(),
                         name: command.name.to_owned(),
                         menu: command.menu.to_owned(),
-                        reason: Reason("Restaurant already exists".to_string()),
-                    })])
-                } else {
-                    Ok(vec![RestaurantEvent::Created(RestaurantCreated {
+                        pricing: command.pricing.to_owned(),
+                    })]),
+                    Err(reason) => Ok(vec![RestaurantEvent::NotCreated(RestaurantNotCreated {
                         identifier: command.identifier.to_owned(),
                         name: command.name.to_owned(),
                         menu: command.menu.to_owned(),
-                    })])
+                        pricing: command.pricing.to_owned(),
+                        reason,
+                    })]),
                 }
             }
             RestaurantCommand::ChangeMenu(command) => {
-                if state.is_some() {
-                    Ok(vec![RestaurantEvent::MenuChanged(RestaurantMenuChanged {
+                let checked = match state {
+                    Some(_) => check_menu(&command.menu),
+                    None => Err(Reason("Restaurant does not exist".to_string())),
+                };
+                match checked {
+                    Ok(()) => Ok(vec![RestaurantEvent::MenuChanged(RestaurantMenuChanged {
                         identifier: command.identifier.to_owned(),
                         menu: command.menu.to_owned(),
-                    })])
-                } else {
-                    Ok(vec![RestaurantEvent::MenuNotChanged(
+                    })]),
+                    Err(reason) => Ok(vec![RestaurantEvent::MenuNotChanged(
                         RestaurantMenuNotChanged {
                             identifier: command.identifier.to_owned(),
                             menu: command.menu.to_owned(),
-                            reason: Reason("Restaurant does not exist".to_string()),
+                            reason,
                         },
-                    )])
+                    )]),
                 }
             }
-            RestaurantCommand::PlaceOrder(command) => {
-                if state.is_some() {
-                    Ok(vec![RestaurantEvent::OrderPlaced(OrderPlaced {
+            RestaurantCommand::PlaceOrder(command) => match state {
+                Some(restaurant) => match price_order(restaurant, &command.line_items) {
+                    Ok((line_items, totals)) => {
+                        Ok(vec![RestaurantEvent::OrderPlaced(OrderPlaced {
+                            identifier: command.identifier.to_owned(),
+                            order_identifier: command.order_identifier.to_owned(),
+                            line_items,
+                            placed_at: command.placed_at,
+                            totals: Some(totals),
+                        })])
+                    }
+                    Err(reason) => Ok(vec![RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
                         identifier: command.identifier.to_owned(),
                         order_identifier: command.order_identifier.to_owned(),
                         line_items: command.line_items.to_owned(),
-                    })])
-                } else {
-                    Ok(vec![RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
+                        reason,
+                    })]),
+                },
+                None => Ok(vec![RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
+                    identifier: command.identifier.to_owned(),
+                    order_identifier: command.order_identifier.to_owned(),
+                    line_items: command.line_items.to_owned(),
+                    reason: Reason("Restaurant does not exist".to_string()),
+                })]),
+            },
+            RestaurantCommand::RevokeOrder(command) => {
+                match check_revocable(state, &command.order_identifier) {
+                    Ok(()) => Ok(vec![RestaurantEvent::OrderRevoked(OrderRevoked {
                         identifier: command.identifier.to_owned(),
                         order_identifier: command.order_identifier.to_owned(),
-                        line_items: command.line_items.to_owned(),
-                        reason: Reason("Restaurant does not exist".to_string()),
-                    })])
+                        reason: command.reason.to_owned(),
+                    })]),
+                    Err(reason) => Ok(vec![RestaurantEvent::OrderNotRevoked(OrderNotRevoked {
+                        identifier: command.identifier.to_owned(),
+                        order_identifier: command.order_identifier.to_owned(),
+                        reason,
+                    })]),
+                }
+            }
+            RestaurantCommand::ChangePricing(command) => {
+                let checked = match state {
+                    Some(_) => check_pricing(&command.pricing),
+                    None => Err(Reason("Restaurant does not exist".to_string())),
+                };
+                match checked {
+                    Ok(()) => Ok(vec![RestaurantEvent::PricingChanged(
+                        RestaurantPricingChanged {
+                            identifier: command.identifier.to_owned(),
+                            pricing: command.pricing.to_owned(),
+                        },
+                    )]),
+                    Err(reason) => Ok(vec![RestaurantEvent::PricingNotChanged(
+                        RestaurantPricingNotChanged {
+                            identifier: command.identifier.to_owned(),
+                            pricing: command.pricing.to_owned(),
+                            reason,
+                        },
+                    )]),
                 }
             }
         }),
@@ -80,6 +254,9 @@ pub fn restaurant_decider<'a>() -> RestaurantDecider<'a> {
                 identifier: event.identifier.to_owned(),
                 name: event.name.to_owned(),
                 menu: event.menu.to_owned(),
+                pricing: event.pricing.to_owned(),
+                placed_orders: vec![],
+                revoked_orders: vec![],
             }),
             // On error event we choose NOT TO change the state of the Restaurant, for example.
             RestaurantEvent::NotCreated(..) => state.clone(),
@@ -88,17 +265,35 @@ pub fn restaurant_decider<'a>() -> RestaurantDecider<'a> {
                 identifier: event.identifier.to_owned(),
                 name: s.name,
                 menu: event.menu.to_owned(),
+                pricing: s.pricing,
+                ..s
             }),
             // On error event we choose NOT TO change the state of the Restaurant, for example.
             RestaurantEvent::MenuNotChanged(..) => state.clone(),
 
-            RestaurantEvent::OrderPlaced(event) => state.clone().map(|s| Restaurant {
+            RestaurantEvent::OrderPlaced(event) => state.clone().map(|mut s| {
+                s.placed_orders.push(event.order_identifier.to_owned());
+                s
+            }),
+            // On error event we choose NOT TO change the state of the Restaurant, for example.
+            RestaurantEvent::OrderNotPlaced(..) => state.clone(),
+
+            RestaurantEvent::OrderRevoked(event) => state.clone().map(|mut s| {
+                s.revoked_orders.push(event.order_identifier.to_owned());
+                s
+            }),
+            // On error event we choose NOT TO change the state of the Restaurant, for example.
+            RestaurantEvent::OrderNotRevoked(..) => state.clone(),
+
+            RestaurantEvent::PricingChanged(event) => state.clone().map(|s| Restaurant {
                 identifier: event.identifier.to_owned(),
                 name: s.name,
                 menu: s.menu,
+                pricing: event.pricing.to_owned(),
+                ..s
             }),
             // On error event we choose NOT TO change the state of the Restaurant, for example.
-            RestaurantEvent::OrderNotPlaced(..) => state.clone(),
+            RestaurantEvent::PricingNotChanged(..) => state.clone(),
         }),
 
         // The initial state of the decider
@@ -109,13 +304,19 @@ pub fn restaurant_decider<'a>() -> RestaurantDecider<'a> {
 #[cfg(test)]
 /// Tests for the Restaurant decider
 mod restaurant_decider_tests {
+    use chrono::{TimeZone, Utc};
     use fmodel_rust::decider::{EventComputation, StateComputation};
+    use rust_decimal::Decimal;
     use uuid::Uuid;
 
     use crate::domain::api::{
-        ChangeRestaurantMenu, CreateRestaurant, MenuId, MenuItem, MenuItemId, MenuItemName, Money,
-        RestaurantCommand, RestaurantCreated, RestaurantEvent, RestaurantId, RestaurantMenu,
-        RestaurantMenuChanged, RestaurantMenuCuisine, RestaurantName,
+        ChangeRestaurantMenu, ChangeRestaurantPricing, CreateRestaurant, Currency, MenuId,
+        MenuItem, MenuItemId, MenuItemName, Money, OrderId, OrderLineItem, OrderLineItemId,
+        OrderLineItemQuantity, OrderNotPlaced, OrderNotRevoked, OrderPlaced, OrderRevoked,
+        OrderTotals, PlaceOrder, Reason, RestaurantCommand, RestaurantCreated, RestaurantEvent,
+        RestaurantId, RestaurantMenu, RestaurantMenuChanged, RestaurantMenuCuisine,
+        RestaurantMenuNotChanged, RestaurantName, RestaurantNotCreated, RestaurantPricing,
+        RestaurantPricingChanged, RestaurantPricingNotChanged, RevokeOrder,
     };
     use crate::domain::restaurant_decider::{restaurant_decider, Restaurant, RestaurantDecider};
 
@@ -132,7 +333,7 @@ mod restaurant_decider_tests {
         let menu_items = vec![MenuItem {
             id: menu_item_id,
             name: MenuItemName("Item 1".to_string()),
-            price: Money(100.1),
+            price: Money::new(Decimal::new(1001, 1), Currency::default()),
         }];
 
         // The command to create an order - CreateOrder
@@ -145,6 +346,7 @@ mod restaurant_decider_tests {
                     items: menu_items.clone(),
                     cuisine: RestaurantMenuCuisine::Vietnamese,
                 },
+                pricing: RestaurantPricing::default(),
             });
         // ### EventSourced flavour ### - Test the decider: given EVENTS, when COMMAND, then NEW EVENTS
         let new_events = decider.compute_new_events(&[], &create_restaurant_command);
@@ -158,6 +360,7 @@ mod restaurant_decider_tests {
                     items: menu_items.clone(),
                     cuisine: RestaurantMenuCuisine::Vietnamese,
                 },
+                pricing: RestaurantPricing::default(),
             })])
         );
         // ### StateStored flavour ### - Test the decider: given STATE, when COMMAND, then NEW STATE
@@ -172,6 +375,9 @@ mod restaurant_decider_tests {
                     items: menu_items.clone(),
                     cuisine: RestaurantMenuCuisine::Vietnamese,
                 },
+                pricing: RestaurantPricing::default(),
+                placed_orders: vec![],
+                revoked_orders: vec![],
             }))
         );
 
@@ -194,6 +400,7 @@ mod restaurant_decider_tests {
                 items: menu_items.clone(),
                 cuisine: RestaurantMenuCuisine::Vietnamese,
             },
+            pricing: RestaurantPricing::default(),
         })];
         let new_events = decider.compute_new_events(&old_events, &change_restaurant_menu);
         assert_eq!(
@@ -217,6 +424,9 @@ mod restaurant_decider_tests {
                 items: menu_items.clone(),
                 cuisine: RestaurantMenuCuisine::Vietnamese,
             },
+            pricing: RestaurantPricing::default(),
+            placed_orders: vec![],
+            revoked_orders: vec![],
         });
         let new_state = decider.compute_new_state(Some(old_state), &change_restaurant_menu);
         assert_eq!(
@@ -229,7 +439,424 @@ mod restaurant_decider_tests {
                     items: menu_items.clone(),
                     cuisine: RestaurantMenuCuisine::Japanese,
                 },
+                pricing: RestaurantPricing::default(),
+                placed_orders: vec![],
+                revoked_orders: vec![],
             }))
         );
     }
+
+    #[test]
+    fn place_order_test() {
+        // The Restaurant decider
+        let decider: RestaurantDecider = restaurant_decider();
+        // The data
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_identifier =
+            OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+        let menu_item_id =
+            MenuItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708210").unwrap());
+        let unknown_menu_item_id =
+            MenuItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708211").unwrap());
+        let first_line_item_id =
+            OrderLineItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708212").unwrap());
+        let second_line_item_id =
+            OrderLineItemId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708213").unwrap());
+        let placed_at = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        // 20% tax, 10% service charge
+        let pricing = RestaurantPricing {
+            tax_rate: Decimal::new(20, 2),
+            service_charge_rate: Decimal::new(10, 2),
+        };
+        let old_events = vec![RestaurantEvent::Created(RestaurantCreated {
+            identifier: restaurant_identifier.clone(),
+            name: RestaurantName("Restaurant 1".to_string()),
+            menu: RestaurantMenu {
+                menu_id: MenuId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708214").unwrap()),
+                items: vec![MenuItem {
+                    id: menu_item_id.clone(),
+                    name: MenuItemName("Item 1".to_string()),
+                    price: Money::new(Decimal::new(1001, 1), Currency::default()),
+                }],
+                cuisine: RestaurantMenuCuisine::Vietnamese,
+            },
+            pricing: pricing.clone(),
+        })];
+
+        // The name and the price of the line item are the ones of the menu, and the totals are priced by the restaurant pricing
+        let place_order: RestaurantCommand = RestaurantCommand::PlaceOrder(PlaceOrder {
+            identifier: restaurant_identifier.clone(),
+            order_identifier: order_identifier.clone(),
+            line_items: vec![OrderLineItem {
+                id: first_line_item_id.clone(),
+                quantity: OrderLineItemQuantity(2),
+                menu_item_id: menu_item_id.clone(),
+                name: MenuItemName("Cheap item".to_string()),
+                price: Some(Money::new(Decimal::new(1, 1), Currency::default())),
+            }],
+            placed_at: Some(placed_at),
+        });
+        let new_events = decider.compute_new_events(&old_events, &place_order);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::OrderPlaced(OrderPlaced {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                line_items: vec![OrderLineItem {
+                    id: first_line_item_id.clone(),
+                    quantity: OrderLineItemQuantity(2),
+                    menu_item_id: menu_item_id.clone(),
+                    name: MenuItemName("Item 1".to_string()),
+                    price: Some(Money::new(Decimal::new(1001, 1), Currency::default())),
+                }],
+                placed_at: Some(placed_at),
+                // 2 x 100.10 = 200.20; service charge 20.02; tax (200.20 + 20.02) x 0.20 = 44.044 -> 44.04
+                totals: Some(OrderTotals {
+                    subtotal: Money::new(Decimal::new(20020, 2), Currency::default()),
+                    service_charge: Money::new(Decimal::new(2002, 2), Currency::default()),
+                    tax: Money::new(Decimal::new(4404, 2), Currency::default()),
+                    total: Money::new(Decimal::new(26426, 2), Currency::default()),
+                    tax_rate: pricing.tax_rate,
+                    service_charge_rate: pricing.service_charge_rate,
+                }),
+            })])
+        );
+
+        // The order of nothing is not placed
+        let place_empty_order: RestaurantCommand = RestaurantCommand::PlaceOrder(PlaceOrder {
+            identifier: restaurant_identifier.clone(),
+            order_identifier: order_identifier.clone(),
+            line_items: vec![],
+            placed_at: Some(placed_at),
+        });
+        let new_events = decider.compute_new_events(&old_events, &place_empty_order);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                line_items: vec![],
+                reason: Reason("Order has no line items".to_string()),
+            })])
+        );
+
+        // Every invalid line item is reported
+        let line_items = vec![
+            OrderLineItem {
+                id: first_line_item_id.clone(),
+                quantity: OrderLineItemQuantity(0),
+                menu_item_id: menu_item_id.clone(),
+                name: MenuItemName("Item 1".to_string()),
+                price: None,
+            },
+            OrderLineItem {
+                id: second_line_item_id.clone(),
+                quantity: OrderLineItemQuantity(1),
+                menu_item_id: unknown_menu_item_id.clone(),
+                name: MenuItemName("Item 2".to_string()),
+                price: None,
+            },
+        ];
+        let place_invalid_order: RestaurantCommand = RestaurantCommand::PlaceOrder(PlaceOrder {
+            identifier: restaurant_identifier.clone(),
+            order_identifier: order_identifier.clone(),
+            line_items: line_items.clone(),
+            placed_at: Some(placed_at),
+        });
+        let new_events = decider.compute_new_events(&old_events, &place_invalid_order);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                line_items,
+                reason: Reason(format!(
+                    "Line item {first_line_item_id}: quantity must be greater than zero; Line item {second_line_item_id}: menu item {unknown_menu_item_id} is not on the menu"
+                )),
+            })])
+        );
+
+        // The order whose totals overflow is not placed - the menu item priced before the prices were checked
+        let overpriced_events = vec![RestaurantEvent::Created(RestaurantCreated {
+            identifier: restaurant_identifier.clone(),
+            name: RestaurantName("Restaurant 1".to_string()),
+            menu: RestaurantMenu {
+                menu_id: MenuId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708214").unwrap()),
+                items: vec![MenuItem {
+                    id: menu_item_id.clone(),
+                    name: MenuItemName("Item 1".to_string()),
+                    price: Money::new(Decimal::MAX, Currency::default()),
+                }],
+                cuisine: RestaurantMenuCuisine::Vietnamese,
+            },
+            pricing: pricing.clone(),
+        })];
+        let new_events = decider.compute_new_events(&overpriced_events, &place_order);
+        let RestaurantCommand::PlaceOrder(PlaceOrder { line_items, .. }) = &place_order else {
+            unreachable!()
+        };
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::OrderNotPlaced(OrderNotPlaced {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                line_items: line_items.clone(),
+                reason: Reason("Order totals are too large".to_string()),
+            })])
+        );
+    }
+
+    #[test]
+    fn invalid_menu_test() {
+        // The Restaurant decider
+        let decider: RestaurantDecider = restaurant_decider();
+        // The data
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let menu_item_ids: Vec<MenuItemId> = (0..4)
+            .map(|index| {
+                MenuItemId(
+                    Uuid::parse_str(&format!("02f09a3f-1624-3b1d-8409-44eff770821{index}"))
+                        .unwrap(),
+                )
+            })
+            .collect();
+        let menu_item = |index: usize, price: Money| MenuItem {
+            id: menu_item_ids[index].clone(),
+            name: MenuItemName(format!("Item {index}")),
+            price,
+        };
+        let eur = Currency::default();
+        let usd = Currency::try_from("USD".to_string()).unwrap();
+        // Negative, too high, and in the currency different from the rest of the menu
+        let menu = RestaurantMenu {
+            menu_id: MenuId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708214").unwrap()),
+            items: vec![
+                menu_item(0, Money::new(Decimal::new(1090, 2), eur.clone())),
+                menu_item(1, Money::new(Decimal::new(-1, 0), eur.clone())),
+                menu_item(2, Money::new(Decimal::MAX, eur.clone())),
+                menu_item(3, Money::new(Decimal::new(1090, 2), usd)),
+            ],
+            cuisine: RestaurantMenuCuisine::Vietnamese,
+        };
+        let reason = Reason(format!(
+            "Menu item {}: price -1 EUR must not be negative; Menu item {}: price {} EUR must not exceed 1000000; Menu item {}: price 10.90 USD must be in EUR, as the rest of the menu",
+            menu_item_ids[1], menu_item_ids[2], Decimal::MAX, menu_item_ids[3]
+        ));
+
+        // The restaurant with the invalid menu is not created
+        let create_restaurant: RestaurantCommand =
+            RestaurantCommand::CreateRestaurant(CreateRestaurant {
+                identifier: restaurant_identifier.clone(),
+                name: RestaurantName("Restaurant 1".to_string()),
+                menu: menu.clone(),
+                pricing: RestaurantPricing::default(),
+            });
+        let new_events = decider.compute_new_events(&[], &create_restaurant);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::NotCreated(RestaurantNotCreated {
+                identifier: restaurant_identifier.clone(),
+                name: RestaurantName("Restaurant 1".to_string()),
+                menu: menu.clone(),
+                pricing: RestaurantPricing::default(),
+                reason: reason.clone(),
+            })])
+        );
+
+        // The menu is not changed to the invalid one
+        let old_events = vec![RestaurantEvent::Created(RestaurantCreated {
+            identifier: restaurant_identifier.clone(),
+            name: RestaurantName("Restaurant 1".to_string()),
+            menu: RestaurantMenu {
+                items: menu.items[..1].to_vec(),
+                ..menu.clone()
+            },
+            pricing: RestaurantPricing::default(),
+        })];
+        let change_menu: RestaurantCommand = RestaurantCommand::ChangeMenu(ChangeRestaurantMenu {
+            identifier: restaurant_identifier.clone(),
+            menu: menu.clone(),
+        });
+        let new_events = decider.compute_new_events(&old_events, &change_menu);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::MenuNotChanged(
+                RestaurantMenuNotChanged {
+                    identifier: restaurant_identifier.clone(),
+                    menu,
+                    reason,
+                }
+            )])
+        );
+    }
+
+    #[test]
+    fn revoke_order_test() {
+        // The Restaurant decider
+        let decider: RestaurantDecider = restaurant_decider();
+        // The data
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_identifier =
+            OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+        let unknown_order_identifier =
+            OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708215").unwrap());
+        let reason = Reason("Out of stock".to_string());
+        let restaurant_created = RestaurantEvent::Created(RestaurantCreated {
+            identifier: restaurant_identifier.clone(),
+            name: RestaurantName("Restaurant 1".to_string()),
+            menu: RestaurantMenu {
+                menu_id: MenuId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708214").unwrap()),
+                items: vec![],
+                cuisine: RestaurantMenuCuisine::Vietnamese,
+            },
+            pricing: RestaurantPricing::default(),
+        });
+        let order_placed = RestaurantEvent::OrderPlaced(OrderPlaced {
+            identifier: restaurant_identifier.clone(),
+            order_identifier: order_identifier.clone(),
+            line_items: vec![],
+            placed_at: None,
+            totals: None,
+        });
+        let order_revoked = RestaurantEvent::OrderRevoked(OrderRevoked {
+            identifier: restaurant_identifier.clone(),
+            order_identifier: order_identifier.clone(),
+            reason: reason.clone(),
+        });
+        let revoke_order = |order_identifier: &OrderId| {
+            RestaurantCommand::RevokeOrder(RevokeOrder {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                reason: reason.clone(),
+            })
+        };
+        let order_not_revoked = |order_identifier: &OrderId, reason: &str| {
+            Ok(vec![RestaurantEvent::OrderNotRevoked(OrderNotRevoked {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                reason: Reason(reason.to_string()),
+            })])
+        };
+
+        // The order placed at the restaurant is revoked
+        let new_events = decider.compute_new_events(
+            &[restaurant_created.clone(), order_placed.clone()],
+            &revoke_order(&order_identifier),
+        );
+        assert_eq!(new_events, Ok(vec![order_revoked.clone()]));
+
+        // The order not placed at the restaurant is not revoked
+        let new_events = decider.compute_new_events(
+            &[restaurant_created.clone(), order_placed.clone()],
+            &revoke_order(&unknown_order_identifier),
+        );
+        assert_eq!(
+            new_events,
+            order_not_revoked(
+                &unknown_order_identifier,
+                &format!("Order {unknown_order_identifier} was not placed at the restaurant"),
+            )
+        );
+
+        // The order revoked already is not revoked again
+        let new_events = decider.compute_new_events(
+            &[restaurant_created, order_placed, order_revoked],
+            &revoke_order(&order_identifier),
+        );
+        assert_eq!(
+            new_events,
+            order_not_revoked(
+                &order_identifier,
+                &format!("Order {order_identifier} is revoked already"),
+            )
+        );
+
+        // The order of the restaurant that does not exist is not revoked
+        let new_events = decider.compute_new_events(&[], &revoke_order(&order_identifier));
+        assert_eq!(
+            new_events,
+            order_not_revoked(&order_identifier, "Restaurant does not exist")
+        );
+    }
+
+    #[test]
+    fn change_pricing_test() {
+        // The Restaurant decider
+        let decider: RestaurantDecider = restaurant_decider();
+        // The data
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let old_events = vec![RestaurantEvent::Created(RestaurantCreated {
+            identifier: restaurant_identifier.clone(),
+            name: RestaurantName("Restaurant 1".to_string()),
+            menu: RestaurantMenu {
+                menu_id: MenuId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708214").unwrap()),
+                items: vec![],
+                cuisine: RestaurantMenuCuisine::Vietnamese,
+            },
+            pricing: RestaurantPricing::default(),
+        })];
+
+        let pricing = RestaurantPricing {
+            tax_rate: Decimal::new(20, 2),
+            service_charge_rate: Decimal::new(125, 3),
+        };
+        let change_pricing: RestaurantCommand =
+            RestaurantCommand::ChangePricing(ChangeRestaurantPricing {
+                identifier: restaurant_identifier.clone(),
+                pricing: pricing.clone(),
+            });
+        let new_events = decider.compute_new_events(&old_events, &change_pricing);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::PricingChanged(
+                RestaurantPricingChanged {
+                    identifier: restaurant_identifier.clone(),
+                    pricing: pricing.clone(),
+                }
+            )])
+        );
+
+        // The pricing of the restaurant that does not exist is not changed
+        let new_events = decider.compute_new_events(&[], &change_pricing);
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::PricingNotChanged(
+                RestaurantPricingNotChanged {
+                    identifier: restaurant_identifier.clone(),
+                    pricing: pricing.clone(),
+                    reason: Reason("Restaurant does not exist".to_string()),
+                }
+            )])
+        );
+
+        // Every invalid rate is reported
+        let invalid_pricing = RestaurantPricing {
+            tax_rate: Decimal::new(-1, 2),
+            service_charge_rate: Decimal::new(15, 1),
+        };
+        let new_events = decider.compute_new_events(
+            &old_events,
+            &RestaurantCommand::ChangePricing(ChangeRestaurantPricing {
+                identifier: restaurant_identifier.clone(),
+                pricing: invalid_pricing.clone(),
+            }),
+        );
+        assert_eq!(
+            new_events,
+            Ok(vec![RestaurantEvent::PricingNotChanged(
+                RestaurantPricingNotChanged {
+                    identifier: restaurant_identifier.clone(),
+                    pricing: invalid_pricing,
+                    reason: Reason(
+                        "Tax rate -0.01 must be between 0 and 1; Service charge rate 1.5 must be between 0 and 1"
+                            .to_string()
+                    ),
+                }
+            )])
+        );
+    }
 }
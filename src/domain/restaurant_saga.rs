@@ -0,0 +1,172 @@
+use fmodel_rust::saga::Saga;
+
+use crate::domain::api::{OrderEvent, RestaurantCommand, RevokeOrder};
+
+/// A convenient type alias for the Restaurant saga
+pub type RestaurantSaga<'a> = Saga<'a, OrderEvent, RestaurantCommand>;
+
+/// The Restaurant saga - compensates the order placed at the restaurant, if the order was rejected.
+/// It is a function that takes an event and returns a list of commands.
+pub fn restaurant_saga<'a>() -> RestaurantSaga<'a> {
+    Saga {
+        react: Box::new(|event| match event {
+            // The order is not created only if it exists already - the `OrderPlaced` was redelivered to the Order saga (at-least-once), so there is nothing to compensate
+            OrderEvent::NotCreated(..) => {
+                vec![]
+            }
+            OrderEvent::Rejected(event) => {
+                vec![RestaurantCommand::RevokeOrder(RevokeOrder {
+                    identifier: event.restaurant_identifier.to_owned(),
+                    order_identifier: event.identifier.to_owned(),
+                    reason: event.reason.to_owned(),
+                })]
+            }
+            OrderEvent::Created(..) => {
+                vec![]
+            }
+            OrderEvent::Prepared(..) => {
+                vec![]
+            }
+            OrderEvent::NotPrepared(..) => {
+                vec![]
+            }
+            OrderEvent::Cancelled(..) => {
+                vec![]
+            }
+            OrderEvent::NotCancelled(..) => {
+                vec![]
+            }
+            OrderEvent::NotRejected(..) => {
+                vec![]
+            }
+            OrderEvent::Accepted(..) => {
+                vec![]
+            }
+            OrderEvent::NotAccepted(..) => {
+                vec![]
+            }
+            OrderEvent::PreparationStarted(..) => {
+                vec![]
+            }
+            OrderEvent::PreparationNotStarted(..) => {
+                vec![]
+            }
+            OrderEvent::ReadyForPickup(..) => {
+                vec![]
+            }
+            OrderEvent::NotReadyForPickup(..) => {
+                vec![]
+            }
+            OrderEvent::PickedUp(..) => {
+                vec![]
+            }
+            OrderEvent::NotPickedUp(..) => {
+                vec![]
+            }
+            OrderEvent::Delivered(..) => {
+                vec![]
+            }
+            OrderEvent::NotDelivered(..) => {
+                vec![]
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+/// Tests for the Restaurant saga
+mod restaurant_saga_tests {
+    use fmodel_rust::decider::EventComputation;
+    use uuid::Uuid;
+
+    use crate::domain::api::{
+        OrderEvent, OrderId, OrderNotCreated, OrderPlaced, OrderPrepared, OrderRejected,
+        OrderStatus, Reason, RestaurantCommand, RestaurantEvent, RestaurantId, RevokeOrder,
+    };
+    use crate::domain::order_decider::order_decider;
+    use crate::domain::order_saga::order_saga;
+    use crate::domain::restaurant_saga::{restaurant_saga, RestaurantSaga};
+
+    #[test]
+    fn test() {
+        // The Restaurant saga
+        let saga: RestaurantSaga = restaurant_saga();
+        // The data
+        let restaurant_identifier =
+            RestaurantId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap());
+        let order_identifier =
+            OrderId(Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap());
+
+        let order_not_created_event = OrderEvent::NotCreated(OrderNotCreated {
+            identifier: order_identifier.clone(),
+            restaurant_identifier: restaurant_identifier.clone(),
+            line_items: vec![],
+            reason: Reason("Order already exists".to_string()),
+        });
+        // The order created already by the redelivered `OrderPlaced` is not revoked
+        let commands = (saga.react)(&order_not_created_event);
+        assert_eq!(commands, vec![]);
+
+        let order_rejected_event = OrderEvent::Rejected(OrderRejected {
+            identifier: order_identifier.clone(),
+            restaurant_identifier: restaurant_identifier.clone(),
+            status: OrderStatus::Rejected,
+            reason: Reason("Out of stock".to_string()),
+            at: None,
+        });
+        let commands = (saga.react)(&order_rejected_event);
+        assert_eq!(
+            commands,
+            vec![RestaurantCommand::RevokeOrder(RevokeOrder {
+                identifier: restaurant_identifier.clone(),
+                order_identifier: order_identifier.clone(),
+                reason: Reason("Out of stock".to_string()),
+            })]
+        );
+
+        let order_prepared_event = OrderEvent::Prepared(OrderPrepared {
+            identifier: order_identifier.clone(),
+            status: OrderStatus::Prepared,
+            at: None,
+        });
+        let commands = (saga.react)(&order_prepared_event);
+        assert_eq!(commands, vec![]);
+    }
+
+    #[test]
+    fn redelivered_order_placed_test() {
+        // The Sagas and the Order decider
+        let order_saga = order_saga();
+        let restaurant_saga: RestaurantSaga = restaurant_saga();
+        let order_decider = order_decider();
+        // The data
+        let order_placed_event = RestaurantEvent::OrderPlaced(OrderPlaced {
+            identifier: RestaurantId(
+                Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708208").unwrap(),
+            ),
+            order_identifier: OrderId(
+                Uuid::parse_str("02f09a3f-1624-3b1d-8409-44eff7708207").unwrap(),
+            ),
+            line_items: vec![],
+            placed_at: None,
+            totals: None,
+        });
+
+        // The order is created on the first delivery of the `OrderPlaced`
+        let create_order = (order_saga.react)(&order_placed_event).remove(0);
+        let created = order_decider
+            .compute_new_events(&[], &create_order)
+            .unwrap();
+
+        // The redelivered `OrderPlaced` does not create the order twice, and does not revoke it
+        let redelivered_create_order = (order_saga.react)(&order_placed_event).remove(0);
+        let not_created = order_decider
+            .compute_new_events(&created, &redelivered_create_order)
+            .unwrap();
+        assert!(matches!(
+            not_created.as_slice(),
+            [OrderEvent::NotCreated(..)]
+        ));
+        assert_eq!((restaurant_saga.react)(&not_created[0]), vec![]);
+    }
+}
@@ -1,14 +1,19 @@
 use fmodel_rust::view::View;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::api::{RestaurantEvent, RestaurantId, RestaurantMenu, RestaurantName};
+use crate::domain::api::{
+    RestaurantEvent, RestaurantId, RestaurantMenu, RestaurantName, RestaurantPricing,
+};
 
 /// The state of the Restaurant View is represented by this struct. It belongs to the Domain layer.
+/// The `pricing` is the default (no tax and service charge) for the restaurants projected before it was recorded.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct RestaurantViewState {
     pub identifier: RestaurantId,
     pub name: RestaurantName,
     pub menu: RestaurantMenu,
+    #[serde(default)]
+    pub pricing: RestaurantPricing,
 }
 
 /// A convenient type alias for the Restaurant view
@@ -24,6 +29,7 @@ pub fn restaurant_view<'a>() -> RestaurantView<'a> {
                 identifier: event.identifier.to_owned(),
                 name: event.name.to_owned(),
                 menu: event.menu.to_owned(),
+                pricing: event.pricing.to_owned(),
             }),
             // On error event we choose NOT TO change the state of the RestaurantView, for example.
             RestaurantEvent::NotCreated(..) => state.clone(),
@@ -32,6 +38,7 @@ pub fn restaurant_view<'a>() -> RestaurantView<'a> {
                 identifier: event.identifier.to_owned(),
                 name: s.name,
                 menu: event.menu.to_owned(),
+                pricing: s.pricing,
             }),
             // On error event we choose NOT TO change the state of the RestaurantView, for example.
             RestaurantEvent::MenuNotChanged(..) => state.clone(),
@@ -40,9 +47,28 @@ pub fn restaurant_view<'a>() -> RestaurantView<'a> {
                 identifier: event.identifier.to_owned(),
                 name: s.name,
                 menu: s.menu,
+                pricing: s.pricing,
             }),
             // On error event we choose NOT TO change the state of the RestaurantView, for example.
             RestaurantEvent::OrderNotPlaced(..) => state.clone(),
+
+            RestaurantEvent::OrderRevoked(event) => state.clone().map(|s| RestaurantViewState {
+                identifier: event.identifier.to_owned(),
+                name: s.name,
+                menu: s.menu,
+                pricing: s.pricing,
+            }),
+            // On error event we choose NOT TO change the state of the RestaurantView, for example.
+            RestaurantEvent::OrderNotRevoked(..) => state.clone(),
+
+            RestaurantEvent::PricingChanged(event) => state.clone().map(|s| RestaurantViewState {
+                identifier: event.identifier.to_owned(),
+                name: s.name,
+                menu: s.menu,
+                pricing: event.pricing.to_owned(),
+            }),
+            // On error event we choose NOT TO change the state of the RestaurantView, for example.
+            RestaurantEvent::PricingNotChanged(..) => state.clone(),
         }),
 
         // The initial state of the decider
@@ -54,11 +80,13 @@ pub fn restaurant_view<'a>() -> RestaurantView<'a> {
 /// Tests for the Restaurant view
 mod restaurant_view_tests {
     use fmodel_rust::view::ViewStateComputation;
+    use rust_decimal::Decimal;
     use uuid::Uuid;
 
     use crate::domain::api::{
-        MenuId, MenuItem, MenuItemId, MenuItemName, Money, RestaurantCreated, RestaurantEvent,
-        RestaurantId, RestaurantMenu, RestaurantMenuChanged, RestaurantMenuCuisine, RestaurantName,
+        Currency, MenuId, MenuItem, MenuItemId, MenuItemName, Money, RestaurantCreated,
+        RestaurantEvent, RestaurantId, RestaurantMenu, RestaurantMenuChanged,
+        RestaurantMenuCuisine, RestaurantName, RestaurantPricing, RestaurantPricingChanged,
     };
     use crate::domain::restaurant_view::{restaurant_view, RestaurantView, RestaurantViewState};
 
@@ -75,7 +103,7 @@ mod restaurant_view_tests {
         let menu_items = vec![MenuItem {
             id: menu_item_id,
             name: MenuItemName("Item 1".to_string()),
-            price: Money(100.1),
+            price: Money::new(Decimal::new(1001, 1), Currency::default()),
         }];
 
         // The command to create an order - CreateOrder
@@ -87,6 +115,7 @@ mod restaurant_view_tests {
                 items: menu_items.clone(),
                 cuisine: RestaurantMenuCuisine::Vietnamese,
             },
+            pricing: RestaurantPricing::default(),
         });
         let new_state = view.compute_new_state(None, &[&restaurant_created]);
         assert_eq!(
@@ -99,6 +128,7 @@ mod restaurant_view_tests {
                     items: menu_items.clone(),
                     cuisine: RestaurantMenuCuisine::Vietnamese,
                 },
+                pricing: RestaurantPricing::default(),
             })
         );
 
@@ -118,6 +148,7 @@ mod restaurant_view_tests {
                 items: menu_items.clone(),
                 cuisine: RestaurantMenuCuisine::Vietnamese,
             },
+            pricing: RestaurantPricing::default(),
         });
         let new_state = view.compute_new_state(Some(old_state), &[&menu_changed]);
         assert_eq!(
@@ -130,7 +161,20 @@ mod restaurant_view_tests {
                     items: menu_items.clone(),
                     cuisine: RestaurantMenuCuisine::Japanese,
                 },
+                pricing: RestaurantPricing::default(),
             })
         );
+
+        let pricing = RestaurantPricing {
+            tax_rate: Decimal::new(20, 2),
+            service_charge_rate: Decimal::new(10, 2),
+        };
+        let pricing_changed: RestaurantEvent =
+            RestaurantEvent::PricingChanged(RestaurantPricingChanged {
+                identifier: restaurant_identifier.clone(),
+                pricing: pricing.clone(),
+            });
+        let new_state = view.compute_new_state(Some(new_state), &[&pricing_changed]);
+        assert_eq!(new_state.map(|state| state.pricing), Some(pricing));
     }
 }
@@ -2,35 +2,55 @@
 
 //! This is an entry point for the application.
 
-use std::env::var;
 use std::process::exit;
 use std::sync::{Arc, Once};
 use std::time::Duration;
 
-use crate::adapter::event_stream::saga_stream::stream_events_to_saga;
-use crate::adapter::event_stream::view_stream::stream_events_to_view;
+use crate::adapter::configuration::{AppConfig, CorsConfig, LogFormat, LoggingConfig};
+use crate::adapter::database::entity::ViewEntity;
+use crate::adapter::database::queries::list_views;
+use crate::adapter::database::registry::register_deciders;
+use crate::adapter::database::MIGRATOR;
+use crate::adapter::event_stream::saga_stream::{self, stream_events_to_saga};
+use crate::adapter::event_stream::view_stream::{self, stream_events_to_view};
+use crate::adapter::event_stream::worker::{
+    manage_worker_pools, spawn_worker_pool, WorkerPoolConfig, WorkerPoolManager, WorkerRegistry,
+};
 use crate::adapter::publisher::order_action_publisher::OrderActionPublisher;
+use crate::adapter::publisher::restaurant_action_publisher::RestaurantActionPublisher;
 use crate::adapter::repository::event_repository::AggregateEventRepository;
+use crate::adapter::repository::idempotency_repository::{
+    purge_idempotency_keys_periodically, IdempotencyRepository,
+};
 use crate::adapter::repository::order_view_state_repository::OrderViewStateRepository;
 use crate::adapter::repository::restaurant_view_state_repository::RestaurantViewStateRepository;
+use crate::adapter::telemetry::{request_span, tracer, tracer_provider};
 use crate::adapter::web::handler;
+use crate::adapter::web::metadata::{
+    ACTOR_HEADER, CAUSATION_ID_HEADER, COMMAND_ID_HEADER, CORRELATION_ID_HEADER,
+    IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER,
+};
 use crate::application::api::Application;
+use crate::domain::api::{Event, EventCatalogue};
 use crate::domain::order_decider::order_decider;
 use crate::domain::order_saga::order_saga;
 use crate::domain::order_view::order_view;
 use crate::domain::restaurant_decider::restaurant_decider;
+use crate::domain::restaurant_saga::restaurant_saga;
 use crate::domain::restaurant_view::restaurant_view;
 use actix_cors::Cors;
+use actix_web::dev::Service;
 use actix_web::middleware::Logger;
 use actix_web::{http::header, web, App, HttpServer};
-use adapter::database::error::ErrorMessage;
+use application::error::AppError;
 
 use fmodel_rust::aggregate::EventSourcedAggregate;
 use fmodel_rust::materialized_view::MaterializedView;
 use fmodel_rust::saga_manager::SagaManager;
-use sqlx::{migrate, postgres::PgPoolOptions, Pool, Postgres};
-use tokio::time::sleep;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use tracing_subscriber::layer::SubscriberExt;
 
 mod adapter;
@@ -44,18 +64,32 @@ pub struct Database {
     db: Pool<Postgres>,
 }
 
-/// Database URL environment variable
-pub const DATABASE_URL: &str = "DATABASE_URL";
-
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    // Load the configuration - the configuration file, overridden by the environment variables
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("🔥 {err}");
+            exit(1);
+        }
+    };
+    // Initialize the OTLP tracing - optional, exported only if the collector endpoint is configured
+    let tracer_provider = match tracer_provider() {
+        Ok(tracer_provider) => tracer_provider,
+        Err(err) => {
+            eprintln!("🔥 Failed to create the OTLP exporter: {err}");
+            exit(1);
+        }
+    };
     // Initialize the logger
-    init_logger();
+    init_logger(&config.logging, tracer_provider.as_ref());
     // Initialize the database
-    let database_url = var(DATABASE_URL).expect("DATABASE_URL must be set");
     let pool = match PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
+        .max_connections(config.database.max_connections)
+        .min_connections(config.database.min_connections)
+        .acquire_timeout(config.database.acquire_timeout())
+        .connect(&config.database.url)
         .await
     {
         Ok(pool) => {
@@ -69,7 +103,7 @@ async fn main() -> std::io::Result<()> {
     };
 
     // Run the database migrations
-    match migrate!().run(&pool).await {
+    match MIGRATOR.run(&pool).await {
         Ok(_) => {
             tracing::info!("✅ Migration is successful!");
         }
@@ -79,6 +113,30 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
+    // Register the deciders and the events they can publish
+    match register_deciders(&Event::event_catalogue(), &Database { db: pool.clone() }).await {
+        Ok(registered) => {
+            tracing::info!(
+                "✅ Deciders are registered! {} new event(s)",
+                registered.len()
+            );
+        }
+        Err(err) => {
+            tracing::error!("🔥 Deciders registration failed: {err}");
+            exit(1);
+        }
+    }
+
+    // Retry policy for the command handling on optimistic locking conflicts
+    let retry_policy = config.commands.retry_policy();
+
+    // Idempotency handler - remembers the events produced by the commands within the retention window
+    let idempotency_handler = IdempotencyRepository::new(
+        Database { db: pool.clone() },
+        config.commands.idempotency_retention(),
+        config.commands.idempotency_lease(),
+    );
+
     // ##### COMMAND SIDE - create an aggregate per decider - distributed scenario #####
     // Create the order repository - command side
     let order_event_repository = AggregateEventRepository::new(Database { db: pool.clone() });
@@ -89,92 +147,192 @@ async fn main() -> std::io::Result<()> {
         restaurant_event_repository,
         // Decider
         // Error type needs to match the error type of the aggregate
-        restaurant_decider().map_error(|_| ErrorMessage {
-            message: "Restaurant decider error".to_string(),
-        }),
+        restaurant_decider()
+            .map_error(|_| AppError::Validation("Restaurant decider error".to_string())),
     ));
     // Create the order aggregate - command side
     let order_aggregate = Arc::new(EventSourcedAggregate::new(
         order_event_repository,
         // Decider
         // Error type needs to match the error type of the aggregate
-        order_decider().map_error(|_| ErrorMessage {
-            message: "Order decider error".to_string(),
-        }),
+        order_decider().map_error(|_| AppError::Validation("Order decider error".to_string())),
     ));
 
     // ###### QUERY SIDE ######
     // Create the restaurant query handler -
     let restaurant_query_handler =
         RestaurantViewStateRepository::new(Database { db: pool.clone() });
-    // Create the restaurant view state repository - query side
-    let restaurant_view_state_repository =
-        RestaurantViewStateRepository::new(Database { db: pool.clone() });
-    // Create the restaurant materialized view - query side - handles the events from the event store and projects them into the denormalized state
-    let restaurant_materialized_view = Arc::new(MaterializedView::new(
-        restaurant_view_state_repository,
-        restaurant_view(),
-    ));
     // Create the order query handler - query side
     let order_query_handler = OrderViewStateRepository::new(Database { db: pool.clone() });
-    // Create the order view state repository - query side
-    let order_view_state_repository = OrderViewStateRepository::new(Database { db: pool.clone() });
-    // Create the order materialized view - query side - handles the events from the event store and projects them into the denormalized state
-    let order_materialized_view = Arc::new(MaterializedView::new(
-        order_view_state_repository,
-        order_view(),
-    ));
+
+    // Create the event query handler - event history of the deciders
+    let event_query_handler = AggregateEventRepository::new(Database { db: pool.clone() });
 
     // Action Publisher for the Saga manager
     let order_action_publisher = OrderActionPublisher {
         order_aggregate: order_aggregate.clone(),
+        retry_policy: retry_policy.clone(),
     };
     // Saga manager
     let order_saga_manager = Arc::new(SagaManager::new(order_action_publisher, order_saga()));
+    // Action Publisher for the compensating Saga manager
+    let restaurant_action_publisher = RestaurantActionPublisher {
+        restaurant_aggregate: restaurant_aggregate.clone(),
+        retry_policy: retry_policy.clone(),
+    };
+    // Compensating Saga manager
+    let restaurant_saga_manager = Arc::new(SagaManager::new(
+        restaurant_action_publisher,
+        restaurant_saga(),
+    ));
 
-    // Start a background task for all the event handling and processing
-    // 1. stop signal for canceling background task
-    let background_task_cancellation = CancellationToken::new();
-    let background_task_cancellation_clone = background_task_cancellation.clone();
-    // 2. Spawn the background task
-    let background_task = actix_web::rt::spawn(async move {
-        let db = Database { db: pool.clone() };
-        loop {
-            match stream_events_to_view(
-                restaurant_materialized_view.clone(),
-                order_materialized_view.clone(),
-                &db,
-            )
-            .await
-            {
-                Ok(_) => {}
-                Err(error) => {
-                    tracing::error!("###  View Stream closed with error: {} ###", error.message);
-                    break;
-                }
-            }
-
-            match stream_events_to_saga(order_saga_manager.clone(), &db).await {
-                Ok(_) => {}
-                Err(error) => {
-                    tracing::error!("###  Saga Stream closed with error: {} ###", error.message);
-                    break;
-                }
-            }
+    // Maximum number of events of a single decider stream processed (and acked) at once, and how long the stream is leased meanwhile
+    let (batch_size, lease_duration) = (config.workers.batch_size, config.workers.lease_duration());
 
-            tokio::select! {
-                _ = sleep(Duration::from_secs(1)) => {
-                    tracing::debug!("### Waiting for 1 second ###");
-                    continue;
-                }
+    // Retry policy of the events the views failed to process, before they are moved to the dead letters
+    let dead_letter_policy = config.workers.dead_letter_policy();
 
-                _ = background_task_cancellation_clone.cancelled() => {
-                    tracing::info!("### Gracefully shutting event handler ###");
-                    break;
+    // Start the supervised worker pools for all the event handling and processing - woken by the new events notifications
+    // 1. stop signal for canceling background tasks
+    let background_task_cancellation = CancellationToken::new();
+    // 2. statuses of the workers - reported by the health endpoint
+    let worker_registry = WorkerRegistry::default();
+    // 3. the views registered (or paused) at runtime are handed over to the background task
+    let (worker_pool_manager, worker_pool_commands) =
+        WorkerPoolManager::new(&[view_stream::HANDLER, saga_stream::HANDLER]);
+    // 4. Spawn the worker pool of the view, configured by the `views` row, and streaming to the event handler of the `views.handler` name
+    let start_worker_pool = {
+        let pool = pool.clone();
+        let worker_registry = worker_registry.clone();
+        move |view: &ViewEntity, worker_pool_cancellation: &CancellationToken| {
+            let db = Database { db: pool.clone() };
+            let name = view.view.clone();
+            match view.handler.as_str() {
+                view_stream::HANDLER => spawn_worker_pool(
+                    &view.view,
+                    &worker_pool_config(config.workers.view_concurrency, view),
+                    {
+                        // Create the restaurant and order materialized views - query side - handle the events from the event store and project them into the denormalized state of the (versioned) projection view
+                        let restaurant_materialized_view = Arc::new(MaterializedView::new(
+                            RestaurantViewStateRepository::for_view(
+                                Database { db: pool.clone() },
+                                &view.view,
+                            ),
+                            restaurant_view(),
+                        ));
+                        let order_materialized_view = Arc::new(MaterializedView::new(
+                            OrderViewStateRepository::for_view(
+                                Database { db: pool.clone() },
+                                &view.view,
+                            ),
+                            order_view(),
+                        ));
+                        let pool = pool.clone();
+                        let dead_letter_policy = dead_letter_policy.clone();
+                        move || {
+                            let (
+                                restaurant_materialized_view,
+                                order_materialized_view,
+                                view,
+                                dead_letter_policy,
+                                db,
+                            ) = (
+                                restaurant_materialized_view.clone(),
+                                order_materialized_view.clone(),
+                                name.clone(),
+                                dead_letter_policy.clone(),
+                                Database { db: pool.clone() },
+                            );
+                            async move {
+                                stream_events_to_view(
+                                    restaurant_materialized_view,
+                                    order_materialized_view,
+                                    &view,
+                                    &batch_size,
+                                    &lease_duration,
+                                    &dead_letter_policy,
+                                    &db,
+                                )
+                                .await
+                            }
+                        }
+                    },
+                    &worker_registry,
+                    worker_pool_cancellation,
+                    &db,
+                ),
+                saga_stream::HANDLER => spawn_worker_pool(
+                    &view.view,
+                    &worker_pool_config(config.workers.saga_concurrency, view),
+                    {
+                        let (pool, order_saga_manager, restaurant_saga_manager) = (
+                            pool.clone(),
+                            order_saga_manager.clone(),
+                            restaurant_saga_manager.clone(),
+                        );
+                        let dead_letter_policy = dead_letter_policy.clone();
+                        move || {
+                            let (
+                                order_saga_manager,
+                                restaurant_saga_manager,
+                                view,
+                                dead_letter_policy,
+                                db,
+                            ) = (
+                                order_saga_manager.clone(),
+                                restaurant_saga_manager.clone(),
+                                name.clone(),
+                                dead_letter_policy.clone(),
+                                Database { db: pool.clone() },
+                            );
+                            async move {
+                                stream_events_to_saga(
+                                    order_saga_manager,
+                                    restaurant_saga_manager,
+                                    &view,
+                                    &batch_size,
+                                    &lease_duration,
+                                    &dead_letter_policy,
+                                    &db,
+                                )
+                                .await
+                            }
+                        }
+                    },
+                    &worker_registry,
+                    worker_pool_cancellation,
+                    &db,
+                ),
+                handler => {
+                    tracing::warn!(
+                        "🔥 Unknown event handler '{handler}' of the view {}, skipping",
+                        view.view
+                    );
+                    vec![]
                 }
-            };
+            }
         }
-    });
+    };
+    // 5. Spawn the worker pools of all the views, and of the views registered at runtime
+    let views = match list_views(&Database { db: pool.clone() }).await {
+        Ok(views) => views,
+        Err(err) => {
+            tracing::error!("🔥 Listing the views failed: {err}");
+            exit(1);
+        }
+    };
+    let background_task = actix_web::rt::spawn(manage_worker_pools(
+        views,
+        worker_pool_commands,
+        start_worker_pool,
+        background_task_cancellation.clone(),
+    ));
+    // 6. Purge the expired idempotency keys periodically
+    let purge_task = actix_web::rt::spawn(purge_idempotency_keys_periodically(
+        Database { db: pool.clone() },
+        config.commands.idempotency_retention(),
+        background_task_cancellation.clone(),
+    ));
 
     // Note: web::Data created _outside_ HttpServer::new closure
     // We will write an application with mutable, shared state. First, we define our state and create our handler function
@@ -183,44 +341,106 @@ async fn main() -> std::io::Result<()> {
         order_aggregate: order_aggregate.clone(),
         restaurant_query_handler,
         order_query_handler,
+        event_query_handler,
+        idempotency_handler,
+        retry_policy,
     });
+    let workers = web::Data::new(worker_registry);
+    let worker_pool_manager = web::Data::new(worker_pool_manager);
+    let database = web::Data::new(Database { db: pool.clone() });
+    let health_config = web::Data::new(config.health.clone());
     // Start the HTTP server
+    let cors_config = config.cors.clone();
     HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin("http://localhost:8000")
-            .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
-            .allowed_headers(vec![
-                header::CONTENT_TYPE,
-                header::AUTHORIZATION,
-                header::ACCEPT,
-            ])
-            .supports_credentials();
+        let cors = cors(&cors_config);
         App::new()
             .app_data(application.clone())
+            .app_data(workers.clone())
+            .app_data(worker_pool_manager.clone())
+            .app_data(database.clone())
+            .app_data(health_config.clone())
             .configure(handler::config)
+            .wrap_fn(|request, service| {
+                let span = request_span(&request);
+                service.call(request).instrument(span)
+            })
             .wrap(Logger::default())
             .wrap(cors)
     })
-    .bind(("127.0.0.1", 8000))?
+    .bind((config.http.host.as_str(), config.http.port))?
     .run()
     .await?;
 
     background_task_cancellation.cancel();
 
     background_task.await?;
+    purge_task.await?;
+    if let Some(tracer_provider) = tracer_provider {
+        if let Err(err) = tracer_provider.shutdown() {
+            tracing::warn!("Flushing the traces failed: {err}");
+        }
+    }
     tracing::info!("### Application gracefully shut down ###");
 
     Ok(())
 }
 
-fn init_logger() {
+/// Worker pool configuration of the `view`, with the configured `concurrency`
+fn worker_pool_config(concurrency: usize, view: &ViewEntity) -> WorkerPoolConfig {
+    WorkerPoolConfig {
+        polling_interval: Duration::from_millis(view.pooling_delay.max(1) as u64),
+        concurrency,
+        ..WorkerPoolConfig::default()
+    }
+}
+
+/// Cross-origin resource sharing of the configured origins and methods
+fn cors(config: &CorsConfig) -> Cors {
+    let cors = config
+        .allowed_origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allowed_methods(config.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(vec![
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::ACCEPT,
+        ])
+        // The command metadata and idempotency headers - sent by the client, and returned with the command response
+        .allowed_headers([
+            COMMAND_ID_HEADER,
+            CORRELATION_ID_HEADER,
+            CAUSATION_ID_HEADER,
+            ACTOR_HEADER,
+            IDEMPOTENCY_KEY_HEADER,
+        ])
+        .expose_headers([
+            COMMAND_ID_HEADER,
+            CORRELATION_ID_HEADER,
+            IDEMPOTENT_REPLAYED_HEADER,
+            header::RETRY_AFTER.as_str(),
+        ]);
+    match config.supports_credentials {
+        true => cors.supports_credentials(),
+        false => cors,
+    }
+}
+
+fn init_logger(config: &LoggingConfig, tracer_provider: Option<&SdkTracerProvider>) {
     INIT.call_once(|| {
         let subscriber = tracing_subscriber::registry()
             .with(
                 tracing_subscriber::EnvFilter::try_from_default_env()
-                    .unwrap_or_else(|_| "info".into()), // 👈 Set default to `info`
+                    .unwrap_or_else(|_| config.filter.as_str().into()), // 👈 `RUST_LOG` takes precedence
             )
-            .with(tracing_subscriber::fmt::layer());
+            .with((config.format == LogFormat::Text).then(tracing_subscriber::fmt::layer))
+            .with(
+                (config.format == LogFormat::Json).then(|| tracing_subscriber::fmt::layer().json()),
+            )
+            .with(
+                tracer_provider
+                    .map(|provider| tracing_opentelemetry::layer().with_tracer(tracer(provider))),
+            );
 
         tracing::subscriber::set_global_default(subscriber)
             .expect("setting default subscriber failed");